@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use abstract_interface::Abstract;
+use abstract_std::objects::AssetEntry;
+use cosmrs::proto::cosmos::bank::v1beta1::query_client::QueryClient;
+use cosmrs::proto::cosmos::bank::v1beta1::{Metadata, QueryDenomMetadataRequest};
+use cosmwasm_std::{Decimal, Uint128};
+use cw_asset::AssetInfo;
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::{BankQuerier, WasmQuerier};
+use cw_orch::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{IcaaError, IcaaResult};
+use crate::{chain_id, chain_name};
+
+/// Path of the native decimals file, defaults to [`DEFAULT_DECIMALS_FILE`].
+pub const DECIMALS_FILE_ENV: &str = "ICAA_DECIMALS";
+pub const DEFAULT_DECIMALS_FILE: &str = "decimals.json";
+
+/// Decimals of native denoms the chain has no bank metadata for, by chain id then denom:
+///
+/// ```json
+/// { "juno-1": { "ujuno": 6 }, "archway-1": { "aarch": 18 } }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NativeDecimals(BTreeMap<String, BTreeMap<String, u32>>);
+
+impl NativeDecimals {
+    pub fn load() -> IcaaResult<Self> {
+        let path =
+            std::env::var(DECIMALS_FILE_ENV).unwrap_or_else(|_| DEFAULT_DECIMALS_FILE.to_string());
+        if !Path::new(&path).exists() {
+            return Ok(Self::default());
+        }
+        Ok(
+            serde_json::from_str(&fs::read_to_string(&path).map_err(anyhow::Error::from)?)
+                .map_err(|e| anyhow::anyhow!("invalid decimals file {path}: {e}"))?,
+        )
+    }
+
+    pub fn get(&self, chain_id: &str, denom: &str) -> Option<u32> {
+        self.0.get(chain_id)?.get(denom).copied()
+    }
+}
+
+/// An amount in human units, e.g. `1.5 juno` or `10 archway>arch`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HumanAmount {
+    pub amount: Decimal,
+    pub asset: String,
+}
+
+impl HumanAmount {
+    pub fn new(amount: &str, asset: &str) -> IcaaResult<Self> {
+        Ok(Self {
+            amount: Decimal::from_str(amount)
+                .map_err(|e| IcaaError::InvalidAmount(format!("{amount}: {e}")))?,
+            asset: asset.to_string(),
+        })
+    }
+
+    /// Resolve the asset on `chain` and convert the amount to base units.
+    pub fn resolve(&self, chain: &Daemon) -> IcaaResult<ResolvedAmount> {
+        let (entry, info) = resolve_asset(chain, &self.asset)?;
+        let decimals = decimals(chain, &info)?;
+        let amount = to_base_units(self.amount, decimals)?;
+        if amount.is_zero() {
+            return Err(IcaaError::InvalidAmount(format!(
+                "{} {} is zero in base units",
+                self.amount, entry
+            )));
+        }
+        Ok(ResolvedAmount {
+            entry,
+            info,
+            amount,
+        })
+    }
+}
+
+impl FromStr for HumanAmount {
+    type Err = IcaaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            [amount, asset] => Self::new(amount, asset),
+            _ => Err(IcaaError::InvalidAmount(format!(
+                "expected `<amount> <asset>`, got {s:?}"
+            ))),
+        }
+    }
+}
+
+/// The ANS entry for an asset. Bare symbols are scoped to `chain`, so `juno` on juno is `juno>juno`.
+pub fn asset_entry(chain: &Daemon, asset: &str) -> AssetEntry {
+    if asset.contains('>') {
+        AssetEntry::from(asset)
+    } else {
+        AssetEntry::from(format!("{}>{}", chain_name(chain), asset))
+    }
+}
+
+pub fn resolve_asset(chain: &Daemon, asset: &str) -> IcaaResult<(AssetEntry, AssetInfo)> {
+    let entry = asset_entry(chain, asset);
+    let info = Abstract::load_from(chain.clone())?
+        .ans_host
+        .resolve(&entry)
+        .map_err(|e| IcaaError::AnsResolution {
+            chain: chain_id(chain),
+            entry: entry.to_string(),
+            reason: e.to_string(),
+        })?;
+    Ok((entry, info))
+}
+
+/// How much of a balance to use, evaluated against the balance at execution time.
+///
+/// Parsed from `all`, `all-but <reserve>`, `<percent>%` or an absolute `<amount>`,
+/// with reserves and amounts in human units.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmountSpec {
+    All,
+    AllBut(Decimal),
+    /// Fraction of the balance, `50%` is stored as `0.5`.
+    Percent(Decimal),
+    Exact(Decimal),
+}
+
+impl AmountSpec {
+    /// The amount in base units to use out of `balance`.
+    ///
+    /// `decimals` are only looked up for specs given in human units.
+    pub fn evaluate(
+        &self,
+        balance: Uint128,
+        decimals: impl FnOnce() -> IcaaResult<u32>,
+    ) -> IcaaResult<Uint128> {
+        let amount = match self {
+            AmountSpec::All => balance,
+            AmountSpec::AllBut(reserve) => {
+                let reserve = to_base_units(*reserve, decimals()?)?;
+                balance.checked_sub(reserve).map_err(|_| {
+                    IcaaError::InvalidAmount(format!(
+                        "balance {balance} is below the reserve of {reserve}"
+                    ))
+                })?
+            }
+            AmountSpec::Percent(fraction) => balance * *fraction,
+            AmountSpec::Exact(amount) => {
+                let amount = to_base_units(*amount, decimals()?)?;
+                if amount > balance {
+                    return Err(IcaaError::InvalidAmount(format!(
+                        "requested {amount} but the balance is only {balance}"
+                    )));
+                }
+                amount
+            }
+        };
+        Ok(amount)
+    }
+}
+
+impl FromStr for AmountSpec {
+    type Err = IcaaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parse = |d: &str| {
+            Decimal::from_str(d.trim()).map_err(|e| IcaaError::InvalidAmount(format!("{d:?}: {e}")))
+        };
+        if s == "all" {
+            Ok(AmountSpec::All)
+        } else if let Some(reserve) = s.strip_prefix("all-but") {
+            Ok(AmountSpec::AllBut(parse(reserve)?))
+        } else if let Some(percent) = s.strip_suffix('%') {
+            let fraction = parse(percent)? / Decimal::percent(100);
+            if fraction > Decimal::one() {
+                return Err(IcaaError::InvalidAmount(format!(
+                    "{s} is more than the whole balance"
+                )));
+            }
+            Ok(AmountSpec::Percent(fraction))
+        } else {
+            Ok(AmountSpec::Exact(parse(s)?))
+        }
+    }
+}
+
+/// A [`HumanAmount`] resolved through ANS to an asset and a base-unit amount.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedAmount {
+    pub entry: AssetEntry,
+    pub info: AssetInfo,
+    pub amount: Uint128,
+}
+
+/// Decimals of an asset: CW20s are queried, natives come from the decimals file or the chain's
+/// bank metadata.
+///
+/// Fails with [`IcaaError::UnknownDecimals`] rather than guessing, denoms range from 6 (`ujuno`)
+/// to 18 (`aarch`) decimals.
+pub fn decimals(chain: &Daemon, info: &AssetInfo) -> IcaaResult<u32> {
+    match info {
+        AssetInfo::Native(denom) => {
+            if let Some(decimals) = NativeDecimals::load()?.get(&chain_id(chain), denom) {
+                return Ok(decimals);
+            }
+            denom_metadata(chain, denom)?
+                .and_then(|metadata| display_exponent(&metadata))
+                .ok_or_else(|| IcaaError::UnknownDecimals {
+                    chain: chain_id(chain),
+                    denom: denom.clone(),
+                })
+        }
+        AssetInfo::Cw20(addr) => {
+            let token_info: cw20::TokenInfoResponse = chain
+                .wasm_querier()
+                .smart_query(addr, &cw20::Cw20QueryMsg::TokenInfo {})?;
+            Ok(token_info.decimals as u32)
+        }
+        _ => Err(IcaaError::UnsupportedAsset(info.to_string())),
+    }
+}
+
+/// Bank metadata of `denom`, `None` when the chain has none.
+fn denom_metadata(chain: &Daemon, denom: &str) -> IcaaResult<Option<Metadata>> {
+    let request = QueryDenomMetadataRequest {
+        denom: denom.to_string(),
+    };
+    let channel = chain.channel();
+    let resp = chain
+        .rt_handle
+        .block_on(async move { QueryClient::new(channel).denom_metadata(request).await });
+    match resp {
+        Ok(resp) => Ok(resp.into_inner().metadata),
+        Err(status) if status.code() == tonic::Code::NotFound => Ok(None),
+        Err(status) => Err(anyhow::anyhow!(
+            "failed to query the metadata of {} on {}: {}",
+            denom,
+            chain_id(chain),
+            status
+        )
+        .into()),
+    }
+}
+
+/// Exponent of the display unit, e.g. 6 for `ujuno` displayed as `juno`.
+fn display_exponent(metadata: &Metadata) -> Option<u32> {
+    metadata
+        .denom_units
+        .iter()
+        .find(|unit| unit.denom == metadata.display)
+        .map(|unit| unit.exponent)
+}
+
+/// Convert a human amount to base units of an asset with `decimals` decimals.
+///
+/// Fails when the amount is more precise than the asset or doesn't fit in base units.
+pub fn to_base_units(amount: Decimal, decimals: u32) -> IcaaResult<Uint128> {
+    let out_of_range = || IcaaError::InvalidAmount(format!("{amount} with {decimals} decimals"));
+    let atomics = amount.atomics();
+    if decimals >= Decimal::DECIMAL_PLACES {
+        let factor = 10u128
+            .checked_pow(decimals - Decimal::DECIMAL_PLACES)
+            .ok_or_else(out_of_range)?;
+        return atomics
+            .checked_mul(Uint128::new(factor))
+            .map_err(|_| out_of_range());
+    }
+    let divisor = Uint128::new(10u128.pow(Decimal::DECIMAL_PLACES - decimals));
+    if !(atomics % divisor).is_zero() {
+        return Err(IcaaError::InvalidAmount(format!(
+            "{amount} has more than the {decimals} decimals of the asset"
+        )));
+    }
+    Ok(atomics / divisor)
+}
+
+/// Balance of any native or CW20 asset held by `address`.
+pub fn query_balance(
+    chain: &Daemon,
+    info: &AssetInfo,
+    address: impl Into<String>,
+) -> IcaaResult<Uint128> {
+    let address = address.into();
+    match info {
+        AssetInfo::Native(denom) => Ok(chain
+            .bank_querier()
+            .balance(address, Some(denom.clone()))?
+            .first()
+            .map(|c| c.amount)
+            .unwrap_or_default()),
+        AssetInfo::Cw20(addr) => {
+            let balance: cw20::BalanceResponse = chain
+                .wasm_querier()
+                .smart_query(addr, &cw20::Cw20QueryMsg::Balance { address })?;
+            Ok(balance.balance)
+        }
+        _ => Err(IcaaError::UnsupportedAsset(info.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn base_units_follow_decimals() {
+        assert_eq!(
+            to_base_units(dec("1.5"), 6).unwrap(),
+            Uint128::new(1_500_000)
+        );
+        assert_eq!(
+            to_base_units(dec("10"), 18).unwrap(),
+            Uint128::new(10_000_000_000_000_000_000)
+        );
+        assert_eq!(to_base_units(dec("2"), 0).unwrap(), Uint128::new(2));
+        assert_eq!(
+            to_base_units(dec("1"), 24).unwrap(),
+            Uint128::new(10u128.pow(24))
+        );
+    }
+
+    #[test]
+    fn base_units_reject_extra_precision() {
+        assert!(matches!(
+            to_base_units(dec("1.0000001"), 6),
+            Err(IcaaError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            to_base_units(dec("0.5"), 0),
+            Err(IcaaError::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn base_units_reject_overflow() {
+        assert!(matches!(
+            to_base_units(dec("1"), 39),
+            Err(IcaaError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            to_base_units(dec("1000"), 38),
+            Err(IcaaError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            to_base_units(dec("1"), 255),
+            Err(IcaaError::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn specs_in_base_units_need_no_decimals() {
+        let unknown = || -> IcaaResult<u32> { panic!("decimals looked up") };
+        let balance = Uint128::new(1_000);
+        assert_eq!(AmountSpec::All.evaluate(balance, unknown).unwrap(), balance);
+        assert_eq!(
+            AmountSpec::from_str("50%")
+                .unwrap()
+                .evaluate(balance, unknown)
+                .unwrap(),
+            Uint128::new(500)
+        );
+        assert_eq!(
+            AmountSpec::from_str("all-but 0.0001")
+                .unwrap()
+                .evaluate(balance, || Ok(6))
+                .unwrap(),
+            Uint128::new(900)
+        );
+    }
+
+    #[test]
+    fn display_exponent_uses_the_display_unit() {
+        use cosmrs::proto::cosmos::bank::v1beta1::DenomUnit;
+        let metadata = Metadata {
+            denom_units: vec![
+                DenomUnit {
+                    denom: "aarch".to_string(),
+                    exponent: 0,
+                    aliases: vec![],
+                },
+                DenomUnit {
+                    denom: "arch".to_string(),
+                    exponent: 18,
+                    aliases: vec![],
+                },
+            ],
+            base: "aarch".to_string(),
+            display: "arch".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(display_exponent(&metadata), Some(18));
+    }
+}
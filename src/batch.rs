@@ -0,0 +1,415 @@
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+use abstract_interface::AbstractAccount;
+use abstract_std::ibc_host::HostAction;
+use abstract_std::manager::ModuleInstallConfig;
+use abstract_std::objects::chain_name::ChainName;
+use abstract_std::objects::AssetEntry;
+use abstract_std::{ibc_client, manager, proxy};
+use cosmrs::Any;
+use cosmwasm_std::Coin;
+use cw_asset::AssetInfo;
+use cw_orch::daemon::{CosmTxResponse, Daemon};
+use cw_orch::prelude::*;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use cw_orch_interchain::types::NestedPacketsFlow;
+use log::warn;
+
+use crate::amount::ResolvedAmount;
+use crate::error::{IcaaError, IcaaResult};
+use crate::guard;
+use crate::remote::{exec_on_proxy, register_msg};
+use crate::tx::{bank_send_msg, commit, execute_msg};
+use crate::wait::{self, WaitStrategy};
+use crate::{run_log, transfer};
+
+/// A single step of a bootstrap flow, executed by the wallet that owns the account.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Send funds from the wallet to the account's proxy.
+    Deposit(ResolvedAmount),
+    EnableIbc,
+    RegisterRemote {
+        host_chain: String,
+        base_asset: Option<AssetEntry>,
+        namespace: Option<String>,
+        install_modules: Vec<ModuleInstallConfig>,
+    },
+    SendFunds {
+        host_chain: String,
+        funds: Vec<Coin>,
+    },
+    RemoteDispatch {
+        host_chain: String,
+        manager_msgs: Vec<manager::ExecuteMsg>,
+    },
+}
+
+impl Action {
+    /// The remote chain on which this action needs an existing remote account.
+    fn requires_remote(&self) -> Option<&str> {
+        match self {
+            Action::SendFunds { host_chain, .. } | Action::RemoteDispatch { host_chain, .. } => {
+                Some(host_chain)
+            }
+            _ => None,
+        }
+    }
+
+    fn sends_packets(&self) -> bool {
+        matches!(
+            self,
+            Action::RegisterRemote { .. }
+                | Action::SendFunds { .. }
+                | Action::RemoteDispatch { .. }
+        )
+    }
+
+    /// Check that the wallet can pay for the action, as [`transfer::deposit`] does, and apply
+    /// the mainnet guard to what it spends or executes remotely.
+    fn check(&self, chain: &Daemon) -> IcaaResult<()> {
+        match self {
+            Action::Deposit(funds) => {
+                transfer::check_deposit(chain, funds)?;
+                guard::spend(chain, &funds.info.inner(), funds.amount, "batched deposit")?;
+                Ok(())
+            }
+            Action::SendFunds { host_chain, funds } => {
+                for coin in funds {
+                    guard::spend(
+                        chain,
+                        &coin.denom,
+                        coin.amount,
+                        &format!("batched send to {host_chain}"),
+                    )?;
+                }
+                Ok(())
+            }
+            Action::RemoteDispatch { manager_msgs, .. } => {
+                Ok(guard::check_manager_msgs(chain, manager_msgs)?)
+            }
+            Action::EnableIbc | Action::RegisterRemote { .. } => Ok(()),
+        }
+    }
+
+    fn to_any(&self, chain: &Daemon, account: &AbstractAccount<Daemon>) -> IcaaResult<Any> {
+        let manager = account.manager.addr_str()?;
+        let any = match self {
+            Action::Deposit(funds) => {
+                let proxy = account.proxy.addr_str()?;
+                match &funds.info {
+                    AssetInfo::Native(denom) => {
+                        bank_send_msg(chain, &proxy, &[Coin::new(funds.amount.u128(), denom)])
+                    }
+                    AssetInfo::Cw20(addr) => execute_msg(
+                        chain,
+                        addr.as_str(),
+                        &cw20::Cw20ExecuteMsg::Transfer {
+                            recipient: proxy,
+                            amount: funds.amount,
+                        },
+                        &[],
+                    ),
+                    info => return Err(IcaaError::UnsupportedAsset(info.to_string())),
+                }
+            }
+            Action::EnableIbc => execute_msg(
+                chain,
+                &manager,
+                &manager::ExecuteMsg::UpdateSettings {
+                    ibc_enabled: Some(true),
+                },
+                &[],
+            ),
+            Action::RegisterRemote {
+                host_chain,
+                base_asset,
+                namespace,
+                install_modules,
+            } => execute_msg(
+                chain,
+                &manager,
+                &register_msg(
+                    host_chain,
+                    base_asset.clone(),
+                    namespace.clone(),
+                    install_modules.clone(),
+                )?,
+                &[],
+            ),
+            Action::SendFunds { host_chain, funds } => execute_msg(
+                chain,
+                &manager,
+                &exec_on_proxy(&proxy::ExecuteMsg::IbcAction {
+                    msg: ibc_client::ExecuteMsg::SendFunds {
+                        host_chain: host_chain.clone(),
+                        funds: funds.clone(),
+                    },
+                })?,
+                &[],
+            ),
+            Action::RemoteDispatch {
+                host_chain,
+                manager_msgs,
+            } => execute_msg(
+                chain,
+                &manager,
+                &exec_on_proxy(&proxy::ExecuteMsg::IbcAction {
+                    msg: ibc_client::ExecuteMsg::RemoteAction {
+                        host_chain: host_chain.clone(),
+                        action: HostAction::Dispatch {
+                            manager_msgs: manager_msgs.clone(),
+                        },
+                    },
+                })?,
+                &[],
+            ),
+        }?;
+        Ok(any)
+    }
+}
+
+/// The outcome of one transaction of a [`Batch`].
+pub struct StageResult {
+    pub tx: CosmTxResponse,
+    /// Wall clock time the transaction was submitted at.
+    pub submitted: SystemTime,
+    /// The IBC packets of the stage, `None` if it sent none.
+    pub flow: Option<NestedPacketsFlow<Daemon>>,
+}
+
+/// Accumulates [`Action`]s and submits them in as few transactions as their ordering allows.
+#[derive(Debug, Clone, Default)]
+pub struct Batch {
+    actions: Vec<Action>,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub fn deposit(self, funds: ResolvedAmount) -> Self {
+        self.push(Action::Deposit(funds))
+    }
+
+    pub fn enable_ibc(self) -> Self {
+        self.push(Action::EnableIbc)
+    }
+
+    pub fn register_remote(self, host_chain: impl Into<String>) -> Self {
+        self.push(Action::RegisterRemote {
+            host_chain: host_chain.into(),
+            base_asset: None,
+            namespace: None,
+            install_modules: vec![],
+        })
+    }
+
+    pub fn send_funds(self, host_chain: impl Into<String>, funds: Vec<Coin>) -> Self {
+        self.push(Action::SendFunds {
+            host_chain: host_chain.into(),
+            funds,
+        })
+    }
+
+    pub fn remote_dispatch(
+        self,
+        host_chain: impl Into<String>,
+        manager_msgs: Vec<manager::ExecuteMsg>,
+    ) -> Self {
+        self.push(Action::RemoteDispatch {
+            host_chain: host_chain.into(),
+            manager_msgs,
+        })
+    }
+
+    /// Bootstrap a remote account on `host_chain`: enable IBC and register the remote account
+    /// unless done already, then deposit from the wallet and forward `forward` of it.
+    ///
+    /// `registered` are the chains the account has remote accounts on. An account without IBC has
+    /// none, its remote proxies can't even be listed until IBC is enabled.
+    pub fn bootstrap(
+        ibc_enabled: bool,
+        registered: &[ChainName],
+        host_chain: &str,
+        deposit: Option<ResolvedAmount>,
+        forward: Option<Vec<Coin>>,
+    ) -> Self {
+        let mut batch = Batch::new();
+        if !ibc_enabled {
+            batch = batch.enable_ibc();
+        }
+        let is_registered = ibc_enabled
+            && registered
+                .iter()
+                .any(|chain| chain.to_string() == host_chain);
+        if !is_registered {
+            batch = batch.register_remote(host_chain);
+        }
+        if let Some(deposit) = deposit {
+            batch = batch.deposit(deposit);
+        }
+        if let Some(forward) = forward {
+            batch = batch.send_funds(host_chain, forward);
+        }
+        batch
+    }
+
+    /// Whether the batch registers a remote account on `host_chain`.
+    pub fn registers(&self, host_chain: &str) -> bool {
+        self.actions.iter().any(
+            |action| matches!(action, Action::RegisterRemote { host_chain: h, .. } if h == host_chain),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Split the actions into transactions.
+    ///
+    /// Messages in one transaction run in order, so most actions can share one. The exception is
+    /// acting on a remote account registered earlier in the batch: the ibc-client only knows the
+    /// remote proxy once the registration is acknowledged, so that action starts a new transaction.
+    pub fn stages(&self) -> Vec<Vec<Action>> {
+        let mut stages: Vec<Vec<Action>> = vec![];
+        let mut current = vec![];
+        let mut registering = HashSet::new();
+        for action in &self.actions {
+            if let Some(host_chain) = action.requires_remote() {
+                if registering.contains(host_chain) {
+                    stages.push(std::mem::take(&mut current));
+                    registering.clear();
+                }
+            }
+            if let Action::RegisterRemote { host_chain, .. } = action {
+                registering.insert(host_chain.clone());
+            }
+            current.push(action.clone());
+        }
+        if !current.is_empty() {
+            stages.push(current);
+        }
+        stages
+    }
+
+    /// Submit every stage, waiting for its IBC packets with `strategy` before the next one.
+    ///
+    /// Later stages depend on earlier ones, so stages are never detached from.
+    pub fn submit(
+        self,
+        interchain: &DaemonInterchainEnv,
+        home: &Daemon,
+        account: &AbstractAccount<Daemon>,
+        strategy: &WaitStrategy,
+    ) -> IcaaResult<Vec<StageResult>> {
+        let strategy = strategy.clone().detach(false);
+        for action in &self.actions {
+            action.check(home)?;
+        }
+        let stages = self.stages();
+        let mut results = vec![];
+        for (i, stage) in stages.iter().enumerate() {
+            warn!(
+                "Submitting batch transaction {}/{} with {} messages",
+                i + 1,
+                stages.len(),
+                stage.len()
+            );
+            let msgs = stage
+                .iter()
+                .map(|action| action.to_any(home, account))
+                .collect::<IcaaResult<Vec<_>>>()?;
+            let submitted = SystemTime::now();
+            let tx = commit(home, msgs)?;
+            let step = format!("batch transaction {}/{}", i + 1, stages.len());
+            run_log::tx(&step, home, Some(account.id()?.to_string()), &tx);
+            let flow = if stage.iter().any(Action::sends_packets) {
+                Some(wait::wait(interchain, home, &step, tx.clone(), &strategy)?.done()?)
+            } else {
+                None
+            };
+            results.push(StageResult {
+                tx,
+                submitted,
+                flow,
+            });
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coins, Uint128};
+
+    use super::*;
+
+    fn deposit() -> ResolvedAmount {
+        ResolvedAmount {
+            entry: AssetEntry::from("juno>juno"),
+            info: AssetInfo::native("ujuno"),
+            amount: Uint128::new(1_000_000),
+        }
+    }
+
+    #[test]
+    fn bootstrap_without_ibc_enables_it_and_registers() {
+        let batch = Batch::bootstrap(false, &[], "osmosis", None, None);
+        assert!(batch.registers("osmosis"));
+        let stages = batch.stages();
+        assert_eq!(stages.len(), 1);
+        assert!(matches!(
+            stages[0][..],
+            [Action::EnableIbc, Action::RegisterRemote { .. }]
+        ));
+    }
+
+    #[test]
+    fn bootstrap_without_ibc_forwards_in_a_second_tx() {
+        let batch = Batch::bootstrap(
+            false,
+            &[],
+            "osmosis",
+            Some(deposit()),
+            Some(coins(1_000_000, "ujuno")),
+        );
+        let stages = batch.stages();
+        assert_eq!(stages.len(), 2);
+        assert!(matches!(
+            stages[0][..],
+            [
+                Action::EnableIbc,
+                Action::RegisterRemote { .. },
+                Action::Deposit(_)
+            ]
+        ));
+        assert!(matches!(stages[1][..], [Action::SendFunds { .. }]));
+    }
+
+    #[test]
+    fn bootstrap_skips_what_is_set_up() {
+        let osmosis = ChainName::from_string("osmosis".to_string()).unwrap();
+        let batch = Batch::bootstrap(
+            true,
+            &[osmosis],
+            "osmosis",
+            Some(deposit()),
+            Some(coins(1_000_000, "ujuno")),
+        );
+        assert!(!batch.registers("osmosis"));
+        let stages = batch.stages();
+        assert_eq!(stages.len(), 1);
+        assert!(matches!(
+            stages[0][..],
+            [Action::Deposit(_), Action::SendFunds { .. }]
+        ));
+    }
+}
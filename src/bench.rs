@@ -0,0 +1,445 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use abstract_interface::AbstractAccount;
+use cosmwasm_std::{BankMsg, Coin, CosmosMsg, WasmMsg};
+use cw_orch::daemon::Daemon;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use log::warn;
+use serde::Serialize;
+
+use crate::costs::{CostLedger, Payer};
+use crate::error::IcaaResult;
+use crate::guard;
+use crate::packets::{packet_results, PacketResult};
+use crate::remote::execute_remote;
+use crate::tx::{commit, cosmos_msg_to_any};
+use crate::wait::WaitStrategy;
+use crate::{chain_id, chain_name};
+
+/// What a single execution of the message set cost.
+#[derive(Debug, Clone, Default)]
+pub struct Execution {
+    pub ledger: CostLedger,
+    /// Set when the transaction went through but the remote side failed (error ack, timeout).
+    pub error: Option<String>,
+}
+
+/// A way of executing messages on the remote chain.
+///
+/// Implemented for signing directly on the remote chain and for dispatching through the home
+/// account; any other implementation (e.g. on a mock interchain) can be run by [`run`].
+pub trait ExecutionPath {
+    fn name(&self) -> String;
+
+    /// Execute `msgs` once and wait until the outcome is known on the chain that sent them.
+    fn execute(&self, msgs: &[CosmosMsg]) -> IcaaResult<Execution>;
+}
+
+/// Sign the messages with the wallet on the remote chain.
+pub struct Direct {
+    pub remote: Daemon,
+}
+
+impl ExecutionPath for Direct {
+    fn name(&self) -> String {
+        format!("direct on {}", chain_name(&self.remote))
+    }
+
+    fn execute(&self, msgs: &[CosmosMsg]) -> IcaaResult<Execution> {
+        guard::check_contracts(&self.remote, msgs)?;
+        for msg in msgs {
+            for coin in sent_funds(msg) {
+                guard::spend(
+                    &self.remote,
+                    &coin.denom,
+                    coin.amount,
+                    "bench direct execution",
+                )?;
+            }
+        }
+        let msgs = msgs
+            .iter()
+            .map(|msg| cosmos_msg_to_any(&self.remote, msg))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let tx = commit(&self.remote, msgs)?;
+        let mut ledger = CostLedger::new();
+        ledger.record("direct", &chain_id(&self.remote), &tx);
+        Ok(Execution {
+            ledger,
+            error: None,
+        })
+    }
+}
+
+/// Funds `msg` takes from the wallet.
+fn sent_funds(msg: &CosmosMsg) -> &[Coin] {
+    match msg {
+        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount,
+        CosmosMsg::Wasm(WasmMsg::Execute { funds, .. }) => funds,
+        CosmosMsg::Wasm(WasmMsg::Instantiate { funds, .. }) => funds,
+        _ => &[],
+    }
+}
+
+/// Dispatch the messages from the home account's remote account with [`execute_remote`], or
+/// from the wallet's interchain account for ICS-27 pairs.
+pub struct ViaIbc<'a> {
+    pub interchain: &'a DaemonInterchainEnv,
+    pub home: Daemon,
+    pub account: AbstractAccount<Daemon>,
+    pub host_chain: String,
+    pub strategy: WaitStrategy,
+}
+
+impl ExecutionPath for ViaIbc<'_> {
+    fn name(&self) -> String {
+        format!(
+            "{} via ibc from {}",
+            self.host_chain,
+            chain_name(&self.home)
+        )
+    }
+
+    fn execute(&self, msgs: &[CosmosMsg]) -> IcaaResult<Execution> {
+        for msg in msgs {
+            for coin in sent_funds(msg) {
+                guard::spend(&self.home, &coin.denom, coin.amount, "bench via ibc")?;
+            }
+        }
+        let flow = execute_remote(
+            self.interchain,
+            &self.home,
+            &self.account,
+            &self.host_chain,
+            "bench via ibc",
+            msgs.to_vec(),
+            &self.strategy,
+        )?;
+        let error = packet_results(&flow)
+            .into_iter()
+            .find_map(|packet| match packet {
+                PacketResult::ErrorAck { error, .. } => Some(error),
+                PacketResult::Timeout => Some("packet timed out".to_string()),
+                PacketResult::Success { .. } => None,
+            });
+        let mut ledger = CostLedger::new();
+        ledger.record_flow("via ibc", &flow);
+        Ok(Execution { ledger, error })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchRun {
+    pub path: String,
+    pub iteration: usize,
+    pub ok: bool,
+    pub error: Option<String>,
+    /// Submit until inclusion (direct) or until the ack is back on the home chain (ibc).
+    pub latency_ms: u128,
+    pub gas_used: u64,
+    pub wallet_fees: Vec<Coin>,
+    pub relayer_fees: Vec<Coin>,
+}
+
+/// Execute `msgs` `iterations` times on every path, alternating between paths.
+pub fn run(paths: &[&dyn ExecutionPath], msgs: &[CosmosMsg], iterations: usize) -> Vec<BenchRun> {
+    let mut runs = vec![];
+    for iteration in 0..iterations {
+        for path in paths {
+            let name = path.name();
+            warn!("Benchmark {} run {}/{}", name, iteration + 1, iterations);
+            let start = Instant::now();
+            let result = path.execute(msgs);
+            let latency = start.elapsed();
+            runs.push(match result {
+                Ok(execution) => bench_run(name, iteration, latency, execution),
+                Err(err) => BenchRun {
+                    path: name,
+                    iteration,
+                    ok: false,
+                    error: Some(err.to_string()),
+                    latency_ms: latency.as_millis(),
+                    gas_used: 0,
+                    wallet_fees: vec![],
+                    relayer_fees: vec![],
+                },
+            });
+        }
+    }
+    runs
+}
+
+fn bench_run(path: String, iteration: usize, latency: Duration, execution: Execution) -> BenchRun {
+    let summary = execution.ledger.summary();
+    let fees = |payer: Payer| -> Vec<Coin> {
+        let mut coins: Vec<Coin> = vec![];
+        for ((p, _, denom), amount) in &summary.fees {
+            if *p != payer {
+                continue;
+            }
+            match coins.iter_mut().find(|c| &c.denom == denom) {
+                Some(coin) => coin.amount += *amount,
+                None => coins.push(Coin::new(amount.u128(), denom)),
+            }
+        }
+        coins
+    };
+    BenchRun {
+        path,
+        iteration,
+        ok: execution.error.is_none(),
+        error: execution.error,
+        latency_ms: latency.as_millis(),
+        gas_used: summary.gas_used.values().sum(),
+        wallet_fees: fees(Payer::Wallet),
+        relayer_fees: fees(Payer::Relayer),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchSummary {
+    pub path: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+    pub mean_latency_ms: u128,
+    pub mean_gas_used: u64,
+    /// Mean wallet fee per successful run.
+    pub mean_wallet_fees: Vec<Coin>,
+    /// Mean relayer fee per successful run.
+    pub mean_relayer_fees: Vec<Coin>,
+}
+
+/// Compare the runs of every path.
+pub fn summarize(runs: &[BenchRun]) -> Vec<BenchSummary> {
+    let mut paths: Vec<&str> = vec![];
+    for run in runs {
+        if !paths.contains(&run.path.as_str()) {
+            paths.push(&run.path);
+        }
+    }
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let path_runs: Vec<&BenchRun> = runs.iter().filter(|run| run.path == path).collect();
+            let ok: Vec<&&BenchRun> = path_runs.iter().filter(|run| run.ok).collect();
+            let failures = path_runs.len() - ok.len();
+            let n = ok.len().max(1);
+            let mean_fees = |fees: &dyn Fn(&BenchRun) -> &Vec<Coin>| -> Vec<Coin> {
+                let mut totals: Vec<Coin> = vec![];
+                for coin in ok.iter().flat_map(|run| fees(run)) {
+                    match totals.iter_mut().find(|c| c.denom == coin.denom) {
+                        Some(total) => total.amount += coin.amount,
+                        None => totals.push(coin.clone()),
+                    }
+                }
+                for total in totals.iter_mut() {
+                    total.amount = total.amount.multiply_ratio(1u128, n as u128);
+                }
+                totals
+            };
+            BenchSummary {
+                path: path.to_string(),
+                runs: path_runs.len(),
+                failures,
+                failure_rate: if path_runs.is_empty() {
+                    0.0
+                } else {
+                    failures as f64 / path_runs.len() as f64
+                },
+                mean_latency_ms: ok.iter().map(|run| run.latency_ms).sum::<u128>() / n as u128,
+                mean_gas_used: ok.iter().map(|run| run.gas_used).sum::<u64>() / n as u64,
+                mean_wallet_fees: mean_fees(&|run| &run.wallet_fees),
+                mean_relayer_fees: mean_fees(&|run| &run.relayer_fees),
+            }
+        })
+        .collect()
+}
+
+fn coins_string(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| format!("{}{}", coin.amount, coin.denom))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A CSV text field, quoted with inner quotes doubled as in RFC 4180.
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Every run as CSV, one line per run.
+pub fn to_csv(runs: &[BenchRun]) -> String {
+    let mut csv =
+        String::from("path,iteration,ok,latency_ms,gas_used,wallet_fees,relayer_fees,error\n");
+    for run in runs {
+        // Writing to a String can't fail.
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{}",
+            csv_field(&run.path),
+            run.iteration,
+            run.ok,
+            run.latency_ms,
+            run.gas_used,
+            csv_field(&coins_string(&run.wallet_fees)),
+            csv_field(&coins_string(&run.relayer_fees)),
+            csv_field(run.error.as_deref().unwrap_or_default())
+        );
+    }
+    csv
+}
+
+/// Write every run as CSV, see [`to_csv`].
+pub fn write_csv(path: &Path, runs: &[BenchRun]) -> IcaaResult<()> {
+    fs::write(path, to_csv(runs))?;
+    Ok(())
+}
+
+/// Write the runs and their summary as JSON.
+pub fn write_json(path: &Path, runs: &[BenchRun]) -> IcaaResult<()> {
+    let json = serde_json::json!({
+        "summary": summarize(runs),
+        "runs": runs,
+    });
+    fs::write(
+        path,
+        serde_json::to_string_pretty(&json).map_err(anyhow::Error::from)?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    /// A path replaying scripted outcomes instead of touching a chain.
+    struct Mock {
+        outcomes: RefCell<VecDeque<IcaaResult<Execution>>>,
+    }
+
+    impl Mock {
+        fn new(outcomes: Vec<IcaaResult<Execution>>) -> Self {
+            Self {
+                outcomes: RefCell::new(outcomes.into()),
+            }
+        }
+    }
+
+    impl ExecutionPath for Mock {
+        fn name(&self) -> String {
+            "mock".to_string()
+        }
+
+        fn execute(&self, _msgs: &[CosmosMsg]) -> IcaaResult<Execution> {
+            self.outcomes
+                .borrow_mut()
+                .pop_front()
+                .expect("no scripted outcome left")
+        }
+    }
+
+    fn ok() -> IcaaResult<Execution> {
+        Ok(Execution::default())
+    }
+
+    fn run_with(path: &str, ok: bool, wallet_fees: Vec<Coin>) -> BenchRun {
+        BenchRun {
+            path: path.to_string(),
+            iteration: 0,
+            ok,
+            error: (!ok).then(|| "failed".to_string()),
+            latency_ms: 100,
+            gas_used: 1000,
+            wallet_fees,
+            relayer_fees: vec![],
+        }
+    }
+
+    #[test]
+    fn runs_every_iteration_and_records_failures() {
+        let mock = Mock::new(vec![
+            ok(),
+            Err(anyhow::anyhow!("out of gas").into()),
+            Ok(Execution {
+                ledger: CostLedger::new(),
+                error: Some("packet timed out".to_string()),
+            }),
+        ]);
+        let runs = run(&[&mock], &[], 3);
+        assert_eq!(runs.len(), 3);
+        assert!(runs[0].ok);
+        assert_eq!(runs[1].error.as_deref(), Some("out of gas"));
+        assert_eq!(runs[2].error.as_deref(), Some("packet timed out"));
+        assert!(!runs[2].ok);
+
+        let summary = &summarize(&runs)[0];
+        assert_eq!(summary.runs, 3);
+        assert_eq!(summary.failures, 2);
+    }
+
+    #[test]
+    fn summary_averages_successful_runs() {
+        let runs = vec![
+            run_with("direct", true, vec![Coin::new(100, "ujuno")]),
+            run_with("direct", true, vec![Coin::new(300, "ujuno")]),
+            run_with("direct", false, vec![]),
+            run_with("ibc", false, vec![]),
+        ];
+        let summaries = summarize(&runs);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].mean_wallet_fees, vec![Coin::new(200, "ujuno")]);
+        assert_eq!(summaries[0].failure_rate, 1.0 / 3.0);
+        assert_eq!(summaries[1].failure_rate, 1.0);
+        assert_eq!(summaries[1].mean_latency_ms, 0);
+    }
+
+    #[test]
+    fn no_iterations_summarize_to_nothing() {
+        let mock = Mock::new(vec![]);
+        let runs = run(&[&mock], &[], 0);
+        assert!(runs.is_empty());
+        assert!(summarize(&runs).is_empty());
+    }
+
+    #[test]
+    fn csv_escapes_quotes_commas_and_newlines() {
+        let mut run = run_with("osmosis via ibc from juno", false, vec![]);
+        run.error = Some("error ack: \"invalid\", retry\nlater".to_string());
+        let csv = to_csv(&[run]);
+        let line = csv.lines().nth(1).unwrap();
+        assert_eq!(
+            line,
+            "\"osmosis via ibc from juno\",0,false,100,1000,\"\",\"\",\"error ack: \"\"invalid\"\", retry"
+        );
+        assert!(csv.ends_with("later\"\n"));
+    }
+
+    #[test]
+    fn sent_funds_of_bank_and_wasm_msgs() {
+        let coins = vec![Coin::new(5u128, "uosmo")];
+        let send = CosmosMsg::Bank(BankMsg::Send {
+            to_address: "osmo1recipient".to_string(),
+            amount: coins.clone(),
+        });
+        let execute = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "osmo1contract".to_string(),
+            msg: Default::default(),
+            funds: coins.clone(),
+        });
+        let clear = CosmosMsg::Wasm(WasmMsg::ClearAdmin {
+            contract_addr: "osmo1contract".to_string(),
+        });
+        assert_eq!(sent_funds(&send), coins.as_slice());
+        assert_eq!(sent_funds(&execute), coins.as_slice());
+        assert!(sent_funds(&clear).is_empty());
+    }
+}
@@ -13,10 +13,11 @@ use abstract_core::{manager, PROXY};
 use abstract_interface::{Abstract, AbstractAccount, ManagerExecFns};
 use cosmwasm_std::to_json_binary;
 use cw_orch::daemon::networks::{ARCHWAY_1, OSMOSIS_1};
-use cw_orch_interchain::prelude::{ChannelCreationValidator, DaemonInterchainEnv, InterchainEnv};
 use cw_orch::{contract::Deploy, prelude::*};
-use icaa_scripts::{list_remote_proxies, JUNO_1};
-use pretty_env_logger::env_logger;
+use cw_orch_interchain::prelude::InterchainEnv;
+use icaa_scripts::costs::CostLedger;
+use icaa_scripts::trace::LatencyTrace;
+use icaa_scripts::{list_remote_proxies, runner, signer, JUNO_1};
 use tokio::runtime::Runtime;
 
 const IBC_CLIENT_ID: &str = "abstract:ibc-client";
@@ -26,14 +27,9 @@ const FIRST_HOP_CHAIN_NAME: &str = "archway";
 const SECOND_HOP_CHAIN_NAME: &str = "juno";
 const THIRD_HOP_CHAIN_NAME: &str = "archway";
 
-fn deploy() -> anyhow::Result<()> {
-    let rt = Runtime::new()?;
+fn deploy(rt: &Runtime) -> anyhow::Result<()> {
 
-    let interchain = DaemonInterchainEnv::new(
-        rt.handle(),
-        vec![(JUNO_1, None), (ARCHWAY_1, None), (OSMOSIS_1, None)],
-        &ChannelCreationValidator,
-    )?;
+    let interchain = signer::connect_chains(rt, vec![JUNO_1, ARCHWAY_1, OSMOSIS_1])?;
     // interchain.with_log();
 
     // setup juno chain
@@ -58,10 +54,14 @@ fn deploy() -> anyhow::Result<()> {
     let home_account_id = home_account_client.id()?;
     let home_acc = AbstractAccount::new(&juno_abstr, home_account_id.clone());
 
+    let mut ledger = CostLedger::new();
+    let mut trace = LatencyTrace::new();
+
     // Check and enable IBC
     if !home_acc.manager.is_module_installed(IBC_CLIENT_ID)? {
         println!("Enabling IBC");
-        home_acc.manager.update_settings(Some(true))?;
+        let enable_ibc_tx = home_acc.manager.update_settings(Some(true))?;
+        ledger.record("enable IBC on juno", JUNO_1.chain_id, &enable_ibc_tx);
     }
 
     // CHeck for and register remote account on osmosis
@@ -82,7 +82,9 @@ fn deploy() -> anyhow::Result<()> {
         println!("Registering remote account on archway");
         let remote_acc_tx = home_acc.register_remote_account(FIRST_HOP_CHAIN_NAME)?;
         // @feedback chain id or chain name?
-        interchain.wait_ibc(&home_chain_id, remote_acc_tx)?;
+        let flow = interchain.wait_ibc(&home_chain_id, remote_acc_tx)?;
+        ledger.record_flow("register juno>archway", &flow);
+        trace.record_flow("register juno>archway", None, &flow);
 
         remote_proxies = list_remote_proxies(&juno, &home_acc)?;
         println!("accounts: {:?}", remote_proxies);
@@ -109,7 +111,9 @@ fn deploy() -> anyhow::Result<()> {
                 ibc_enabled: Some(true),
             },
         )?;
-        interchain.wait_ibc(&home_chain_id, enable_ibc_tx)?;
+        let flow = interchain.wait_ibc(&home_chain_id, enable_ibc_tx)?;
+        ledger.record_flow("enable IBC on archway", &flow);
+        trace.record_flow("enable IBC on archway", None, &flow);
     } else {
         println!("Ibc client is installed on Archway!");
     }
@@ -139,7 +143,9 @@ fn deploy() -> anyhow::Result<()> {
         )?;
         let remote_acc_tx = result;
         // @feedback chain id or chain name?
-        interchain.wait_ibc(&home_chain_id, remote_acc_tx)?;
+        let flow = interchain.wait_ibc(&home_chain_id, remote_acc_tx)?;
+        ledger.record_flow("register juno>archway>osmosis", &flow);
+        trace.record_flow("register juno>archway>osmosis", None, &flow);
 
         remote_proxies = list_remote_proxies(&archway, &archway_acc)?;
         println!("archway proxies: {:?}", remote_proxies);
@@ -178,7 +184,9 @@ fn deploy() -> anyhow::Result<()> {
                 },
             })?,
         )?;
-        interchain.wait_ibc(&home_chain_id, enable_ibc_tx)?;
+        let flow = interchain.wait_ibc(&home_chain_id, enable_ibc_tx)?;
+        ledger.record_flow("enable IBC on osmosis", &flow);
+        trace.record_flow("enable IBC on osmosis", None, &flow);
     } else {
         println!("Ibc client is installed on Osmosis!");
     }
@@ -222,7 +230,9 @@ fn deploy() -> anyhow::Result<()> {
         )?;
         let remote_acc_tx = result;
         // @feedback chain id or chain name?
-        interchain.wait_ibc(&home_chain_id, remote_acc_tx)?;
+        let flow = interchain.wait_ibc(&home_chain_id, remote_acc_tx)?;
+        ledger.record_flow("register juno>archway>osmosis>juno", &flow);
+        trace.record_flow("register juno>archway>osmosis>juno", None, &flow);
 
         let remote_proxies = list_remote_proxies(&osmosis, &osmosis_acc)?;
         println!("accounts: {:?}", remote_proxies);
@@ -233,30 +243,13 @@ fn deploy() -> anyhow::Result<()> {
     // Currently send funds, send back
     // maybe Send juno, swap juno for osmo, send back?
 
+    println!("{}", ledger);
+    println!("{}", trace);
+
     Ok(())
 }
 
 // This script aims to test a theory that we can make executing messages on Archway cheap by doing them all over IBC
 fn main() {
-    dotenv().ok();
-    env_logger::init();
-
-    use dotenv::dotenv;
-
-    if let Err(ref err) = deploy() {
-        log::error!("{}", err);
-        err.chain()
-            .skip(1)
-            .for_each(|cause| log::error!("because: {}", cause));
-
-        // The backtrace is not always generated. Try to run this example
-
-        // with `$env:RUST_BACKTRACE=1`.
-
-        //    if let Some(backtrace) = err.un.backtrace() {
-        //        log::debug!("backtrace: {:?}", backtrace);
-        //    }
-
-        ::std::process::exit(1);
-    }
+    runner::run(deploy)
 }
@@ -2,25 +2,20 @@ use std::str::FromStr;
 
 use abstract_client::{AbstractClient, Namespace};
 use abstract_interface::{Abstract, AbstractAccount};
-use abstract_std::{
-    objects::{
-        chain_name::ChainName, AccountId,
-    }, ibc_host::HostAction, PROXY, proxy, ibc_client, manager,
-};
+use abstract_std::objects::{chain_name::ChainName, AccountId};
 use abstract_std::objects::module::ModuleVersion;
-use cosmwasm_std::{to_json_binary, wasm_execute};
-use cw_orch_interchain::prelude::{ChannelCreationValidator, DaemonInterchainEnv, InterchainEnv};
+use cw_orch_interchain::prelude::InterchainEnv;
 use cw_orch::{
     contract::Deploy, daemon::networks::parse_network,
     environment::BankQuerier, prelude::*,
 };
 use cw_orch::environment::{ChainKind, NetworkInfo};
 use log::warn;
-use pretty_env_logger::env_logger;
 use tokio::runtime::Runtime;
-use cw721_base::ExecuteMsg as NftExecuteMsg;
 
-use icaa_scripts::{IBC_CLIENT_ID};
+use icaa_scripts::nft::{self, NftAction};
+use icaa_scripts::wait::WaitStrategy;
+use icaa_scripts::{runner, signer, IBC_CLIENT_ID};
 
 pub const XION_NETWORK: NetworkInfo = NetworkInfo {
     chain_name: "xion",
@@ -44,17 +39,12 @@ const HOME_CHAIN_NAME: &str = XION_NETWORK.chain_name;
 const REMOTE_CHAIN_ID: &str = "pion-1";
 const REMOTE_CHAIN_NAME: &str = "pion";
 const REMOTE_NFT_ADDR: &str = "neutron1d2s4ss5k5wqntnv7zj65q5wj67sjfedvn6wzpr82mqatuksdk6cqjqatcc";
-fn icaa_demo() -> anyhow::Result<()> {
-    let rt = Runtime::new()?;
+fn icaa_demo(rt: &Runtime) -> anyhow::Result<()> {
 
     // Setup interchain environment
     let home_chain_info = XION_TESTNET_1; // let home_chain_info = parse_network(HOME_CHAIN_ID).unwrap();
     let remote_chain_info = parse_network(REMOTE_CHAIN_ID).unwrap();
-    let interchain = DaemonInterchainEnv::new(
-        rt.handle(),
-        vec![(home_chain_info.clone(), None), (remote_chain_info, None)],
-        &ChannelCreationValidator,
-    )?;
+    let interchain = signer::connect_chains(rt, vec![home_chain_info.clone(), remote_chain_info])?;
 
     // Home chain is where all transactions originate
     let home = interchain.chain(HOME_CHAIN_ID)?;
@@ -156,34 +146,26 @@ fn icaa_demo() -> anyhow::Result<()> {
         );
     }
 
-    let remote_nft_tx = home_account_client.as_ref().manager.execute_on_module(
-        PROXY,
-        proxy::ExecuteMsg::IbcAction {
-            msg: ibc_client::ExecuteMsg::RemoteAction {
-                host_chain: REMOTE_CHAIN_NAME.to_string(),
-                action: HostAction::Dispatch {
-                    manager_msgs: vec![manager::ExecuteMsg::ExecOnModule {
-                        module_id: PROXY.to_string(),
-                        exec_msg: to_json_binary(&proxy::ExecuteMsg::ModuleAction {
-                            msgs: vec![wasm_execute(
-                                REMOTE_NFT_ADDR,
-                                &NftExecuteMsg::<Option<Empty>, Empty>::Mint {
-                                    token_id: "disregarded".to_string(),
-                                    owner: "disregarded".to_string(),
-                                    token_uri: None,
-                                    extension: None,
-                                },
-                                vec![],
-                            )?
-                            .into()],
-                        })?,
-                    }],
-                },
-            },
-        },
+    let token_id = format!("icaa-{}", home_acc.id()?.seq());
+    nft::execute(
+        &interchain,
+        &home,
+        &home_acc,
+        REMOTE_CHAIN_NAME,
+        REMOTE_NFT_ADDR,
+        &[NftAction::Mint {
+            token_id: token_id.clone(),
+            owner: None,
+            token_uri: None,
+            metadata: None,
+        }],
+        &WaitStrategy::default(),
     )?;
-
-    interchain.wait_ibc(HOME_CHAIN_ID, remote_nft_tx)?;
+    warn!(
+        "{} is owned by {}",
+        token_id,
+        nft::owner_of(&remote, REMOTE_NFT_ADDR, &token_id)?
+    );
 
     println!("Minted nft");
 
@@ -211,27 +193,7 @@ fn get_remote_balances(
 }
 
 fn main() {
-    dotenv().ok();
-    env_logger::init();
-
-    use dotenv::dotenv;
-
-    if let Err(ref err) = icaa_demo() {
-        log::error!("{}", err);
-        err.chain()
-            .skip(1)
-            .for_each(|cause| log::error!("because: {}", cause));
-
-        // The backtrace is not always generated. Try to run this example
-
-        // with `$env:RUST_BACKTRACE=1`.
-
-        //    if let Some(backtrace) = err.un.backtrace() {
-        //        log::debug!("backtrace: {:?}", backtrace);
-        //    }
-
-        ::std::process::exit(1);
-    }
+    runner::run(icaa_demo)
 }
 
 /*
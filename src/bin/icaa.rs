@@ -0,0 +1,1122 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use log::warn;
+use tokio::runtime::Runtime;
+
+use abstract_std::manager::ModuleInstallConfig;
+use abstract_std::objects::chain_name::ChainName;
+use abstract_std::objects::module::ModuleInfo;
+use cosmwasm_std::{coins, to_json_binary, CosmosMsg, WasmMsg};
+use cw_asset::AssetInfo;
+use cw_orch::prelude::ContractInstance;
+use cw_orch_interchain::prelude::InterchainEnv;
+use icaa_scripts::amount::{AmountSpec, HumanAmount};
+use icaa_scripts::batch::Batch;
+use icaa_scripts::costs::CostLedger;
+use icaa_scripts::error::IcaaError;
+use icaa_scripts::governance::{self, NewOwner};
+use icaa_scripts::nft::{self, NftAction};
+use icaa_scripts::provision::{self, ProvisionStatus};
+use icaa_scripts::recovery;
+use icaa_scripts::remote::{self, RemoteAccountBuilder};
+use icaa_scripts::token::{self, Cw20Action};
+use icaa_scripts::trace::LatencyTrace;
+use icaa_scripts::wait::{self, WaitOutcome, WaitStrategy};
+use icaa_scripts::{
+    bench, connect, discovery, guard, ics27, inspect, list_remote_proxies, load_account, run_log,
+    runner, signer, sub_accounts, sweep, timeout, transfer, IBC_CLIENT_ID,
+};
+
+#[derive(Parser)]
+#[command(name = "icaa", about = "Abstract interchain account operations")]
+struct Cli {
+    /// Home chain id, where all transactions originate
+    #[arg(long, global = true, default_value = "juno-1")]
+    chain: String,
+    /// Stop waiting for IBC packets after this many seconds, the wait can be resumed with `wait`
+    #[arg(long, global = true)]
+    deadline: Option<u64>,
+    /// Don't wait for IBC packets of single actions, resume later with `wait`
+    #[arg(long, global = true)]
+    detach: bool,
+    /// Signer profile to use on every chain, from the signers file
+    #[arg(long, global = true)]
+    signer: Option<String>,
+    /// Don't ask for confirmation of mainnet spends above the guard's thresholds
+    #[arg(long, global = true)]
+    yes_mainnet: bool,
+    /// Also write structured JSON events to a timestamped file under `logs/`
+    #[arg(long, global = true)]
+    json_log: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+impl Cli {
+    fn wait_strategy(&self) -> WaitStrategy {
+        let strategy = WaitStrategy::default().detach(self.detach);
+        match self.deadline {
+            Some(secs) => strategy.deadline(Duration::from_secs(secs)),
+            None => strategy,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Send funds from the wallet to an account, e.g. `deposit --account 48 1.5 juno`
+    Deposit {
+        #[arg(long)]
+        account: u32,
+        amount: String,
+        asset: String,
+    },
+    /// Send funds from an account back to the wallet
+    Withdraw {
+        #[arg(long)]
+        account: u32,
+        amount: String,
+        asset: String,
+    },
+    /// Send funds from an account to its remote account, e.g. `send-funds --account 48 --to osmosis juno all-but 0.1`
+    SendFunds {
+        #[arg(long)]
+        account: u32,
+        /// Remote chain name
+        #[arg(long)]
+        to: String,
+        asset: String,
+        /// `all`, `all-but <reserve>`, `<percent>%` or an amount
+        amount: String,
+        /// Expected relayer/IBC fee in base units
+        #[arg(long, default_value_t = 0)]
+        ibc_fee: u128,
+    },
+    /// List the remote hosts known to the home chain's IBC client and their channels
+    Hosts {
+        /// Also connect to each remote chain and check that its ibc-host is deployed
+        #[arg(long)]
+        check_remote: bool,
+    },
+    /// Register remote accounts on several chains at once, e.g. `provision --account 48 osmosis archway`
+    Provision {
+        #[arg(long)]
+        account: u32,
+        /// Remote chain names
+        #[arg(required = true)]
+        hosts: Vec<String>,
+        /// Submit one transaction per chain instead of batching all registrations
+        #[arg(long)]
+        separate_txs: bool,
+    },
+    /// Deposit, enable IBC, register a remote account and forward the deposit in as few txs as possible
+    Bootstrap {
+        #[arg(long)]
+        account: u32,
+        /// Remote chain name
+        #[arg(long)]
+        host: String,
+        /// Amount to deposit from the wallet, e.g. `"1.5 juno"`
+        #[arg(long)]
+        deposit: Option<String>,
+        /// Forward the (native) deposit to the remote account
+        #[arg(long, requires = "deposit")]
+        send: bool,
+    },
+    /// Register a customized remote account, validating the options against the remote chain first
+    RegisterRemote {
+        #[arg(long)]
+        account: u32,
+        /// Remote chain name
+        #[arg(long)]
+        host: String,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long)]
+        link: Option<String>,
+        /// Namespace to claim on the remote chain
+        #[arg(long)]
+        namespace: Option<String>,
+        /// ANS entry of the remote account's base asset, e.g. `osmosis>osmo`
+        #[arg(long)]
+        base_asset: Option<String>,
+        /// Module ids to install on the remote account (latest version)
+        #[arg(long = "module")]
+        modules: Vec<String>,
+    },
+    /// Manage the sub-accounts of a parent account
+    SubAccounts {
+        /// Parent account sequence
+        #[arg(long)]
+        parent: u32,
+        #[command(subcommand)]
+        action: SubAccountAction,
+    },
+    /// List funds held by all remote accounts (nested ones included) and bring them home
+    SweepRemotes {
+        #[arg(long)]
+        account: u32,
+        /// Also sweep the remote accounts of every sub-account
+        #[arg(long)]
+        sub_accounts: bool,
+        /// Actually send the funds back, without it only the dry-run listing is printed
+        #[arg(long)]
+        execute: bool,
+    },
+    /// Query and move a CW20 held by an account's remote account
+    Cw20 {
+        #[arg(long)]
+        account: u32,
+        /// Remote chain name holding the tokens, e.g. `archway`
+        #[arg(long)]
+        host: String,
+        /// CW20 contract on the remote chain
+        #[arg(long)]
+        token: String,
+        #[command(subcommand)]
+        action: Cw20Command,
+    },
+    /// Mint, move and list cw721 NFTs held by an account's remote account
+    Nft {
+        #[arg(long)]
+        account: u32,
+        /// Remote chain name holding the NFTs, e.g. `pion`
+        #[arg(long)]
+        host: String,
+        #[command(subcommand)]
+        action: NftCommand,
+    },
+    /// Show an account's modules, balances and remote accounts without signing anything
+    Inspect {
+        /// Account sequence or namespace
+        account: String,
+    },
+    /// Show or hand over the governance of an account, e.g. `governance --account 48 transfer multisig juno1...`
+    Governance {
+        #[arg(long)]
+        account: u32,
+        #[command(subcommand)]
+        action: GovernanceCommand,
+    },
+    /// Audit what controls an account's remote accounts and hand them over when its key is rotated or lost
+    Recovery {
+        #[arg(long)]
+        account: u32,
+        #[command(subcommand)]
+        action: RecoveryCommand,
+    },
+    /// Resume waiting for the IBC packets of a detached or expired transaction
+    Wait { txhash: String },
+    /// List transactions whose IBC packets we stopped waiting for
+    Pending,
+    /// Encrypt a mnemonic into a keystore file usable by signer profiles
+    Keystore { path: PathBuf },
+    /// Compare executing a contract msg directly on a remote chain and through the remote account
+    Bench {
+        #[arg(long)]
+        account: u32,
+        /// Remote chain name, e.g. `archway`
+        #[arg(long)]
+        host: String,
+        /// Contract on the remote chain to execute
+        #[arg(long)]
+        contract: String,
+        /// Execute msg, as JSON
+        #[arg(long)]
+        msg: String,
+        #[arg(long, default_value_t = 5)]
+        iterations: usize,
+        /// Results are written to `<out>.csv` and `<out>.json`
+        #[arg(long, default_value = "bench")]
+        out: String,
+    },
+}
+
+/// Amounts are `all`, `all-but <reserve>`, `<percent>%` or an amount, of the remote balance.
+#[derive(Subcommand)]
+enum Cw20Command {
+    /// Show the remote account's balance
+    Balance,
+    Transfer {
+        recipient: String,
+        amount: String,
+    },
+    /// Send to a contract
+    Send {
+        contract: String,
+        amount: String,
+        /// Msg for the contract's `Receive` hook, as JSON
+        #[arg(long, default_value = "{}")]
+        msg: String,
+    },
+    /// Let a spender use part of the balance
+    Allow {
+        spender: String,
+        amount: String,
+    },
+    /// Bridge over ICS-20 through a cw20-ics20 contract on the remote chain
+    Bridge {
+        /// The cw20-ics20 contract
+        #[arg(long)]
+        ics20: String,
+        /// Channel of the cw20-ics20 contract to bridge over
+        #[arg(long)]
+        channel: String,
+        /// Receiver on the other side of the channel
+        remote_address: String,
+        amount: String,
+        /// Packet timeout in seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum NftCommand {
+    /// Mint a token, to the remote account unless `--owner` is given
+    Mint {
+        collection: String,
+        token_id: String,
+        #[arg(long)]
+        owner: Option<String>,
+        #[arg(long)]
+        uri: Option<String>,
+        /// Token metadata, as JSON
+        #[arg(long)]
+        metadata: Option<String>,
+    },
+    /// Transfer a token held by the remote account
+    Transfer {
+        collection: String,
+        token_id: String,
+        recipient: String,
+    },
+    /// Send a token held by the remote account to a contract
+    Send {
+        collection: String,
+        token_id: String,
+        contract: String,
+        /// Msg for the contract's `ReceiveNft` hook, as JSON
+        #[arg(long, default_value = "{}")]
+        msg: String,
+    },
+    /// Show the owner and metadata of a token
+    Owner {
+        collection: String,
+        token_id: String,
+    },
+    /// List the tokens the remote account holds in each collection
+    List { collections: Vec<String> },
+}
+
+#[derive(Subcommand)]
+enum GovernanceCommand {
+    /// Show the owner of the account and its sub-accounts, and how its remote accounts are controlled
+    Show,
+    /// Propose a new owner, nothing changes until it accepts
+    Transfer {
+        #[command(subcommand)]
+        to: NewOwnerArg,
+        /// Blocks the proposal stays valid for
+        #[arg(long)]
+        expiry_blocks: Option<u64>,
+    },
+    /// Accept a pending transfer to the wallet or to a sub-account of an account it owns
+    Accept,
+}
+
+#[derive(Subcommand)]
+enum NewOwnerArg {
+    Monarch {
+        address: String,
+    },
+    /// Make it a sub-account of a parent account
+    SubAccount {
+        parent: u32,
+    },
+    Multisig {
+        address: String,
+    },
+}
+
+impl From<NewOwnerArg> for NewOwner {
+    fn from(arg: NewOwnerArg) -> Self {
+        match arg {
+            NewOwnerArg::Monarch { address } => NewOwner::Monarch(address),
+            NewOwnerArg::SubAccount { parent } => NewOwner::SubAccount(parent),
+            NewOwnerArg::Multisig { address } => NewOwner::Multisig(address),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum RecoveryCommand {
+    /// Show the owners up to the top-level one and whether each remote account is still reachable
+    Audit {
+        /// Also send an empty packet to each remote account
+        #[arg(long)]
+        ping: bool,
+    },
+    /// Hand the account over with the top-level owner's signer, accepting and pinging when the wallet can
+    Start {
+        #[command(subcommand)]
+        to: NewOwnerArg,
+    },
+    /// Accept a started recovery with the new owner's signer and audit the remote accounts
+    Finish {
+        /// Also send an empty packet to each remote account
+        #[arg(long)]
+        ping: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SubAccountAction {
+    /// List sub-accounts with their balances and remote accounts
+    List,
+    /// Get a sub-account by name, recycling a decommissioned one or creating it if needed
+    Reuse { name: String },
+    /// Bring a sub-account's funds home from its remote accounts and move them to the parent
+    Sweep {
+        id: u32,
+        /// CW20 token the sub-account holds, moved to the parent too (repeatable)
+        #[arg(long)]
+        cw20: Vec<String>,
+    },
+    /// Sweep a sub-account and mark it for recycling
+    Decommission {
+        id: u32,
+        /// CW20 token the sub-account holds, moved to the parent too (repeatable)
+        #[arg(long)]
+        cw20: Vec<String>,
+    },
+}
+
+fn run(rt: &Runtime, cli: Cli) -> anyhow::Result<()> {
+    if let Some(profile) = &cli.signer {
+        std::env::set_var(signer::PROFILE_ENV, profile);
+    }
+    if let Command::Keystore { path } = &cli.command {
+        return create_keystore(path);
+    }
+    guard::init(cli.yes_mainnet)?;
+    if matches!(
+        &cli.command,
+        Command::Inspect { .. }
+            | Command::Governance {
+                action: GovernanceCommand::Show,
+                ..
+            }
+            | Command::Recovery {
+                action: RecoveryCommand::Audit { ping: false },
+                ..
+            }
+    ) {
+        std::env::set_var(signer::READ_ONLY_ENV, "1");
+    }
+    let interchain = connect(rt, &cli.chain, &[])?;
+    let home = interchain.chain(&cli.chain)?;
+    let strategy = cli.wait_strategy();
+
+    match cli.command {
+        Command::Deposit {
+            account,
+            amount,
+            asset,
+        } => {
+            let account = load_account(&home, account)?;
+            let funds = HumanAmount::new(&amount, &asset)?.resolve(&home)?;
+            let resp = transfer::deposit(&home, &account, &funds)?;
+            warn!("Deposited in tx {}", resp.txhash);
+        }
+        Command::Withdraw {
+            account,
+            amount,
+            asset,
+        } => {
+            let account = load_account(&home, account)?;
+            let funds = HumanAmount::new(&amount, &asset)?.resolve(&home)?;
+            let resp = transfer::withdraw(&home, &account, &funds)?;
+            warn!("Withdrew in tx {}", resp.txhash);
+        }
+        Command::SendFunds {
+            account,
+            to,
+            asset,
+            amount,
+            ibc_fee,
+        } => {
+            let (interchain, _) = discovery::connect_with_hosts(
+                rt,
+                &cli.chain,
+                &[ChainName::from_string(to.clone())?],
+            )?;
+            let home = interchain.chain(&cli.chain)?;
+            let account = load_account(&home, account)?;
+            let spec = AmountSpec::from_str(&amount)?;
+            let tx = transfer::send_funds(&home, &account, &to, &asset, &spec, ibc_fee.into())?;
+            let step = format!("send funds to {to}");
+            if let WaitOutcome::Done(_) = wait::wait(&interchain, &home, &step, tx, &strategy)? {
+                warn!("Funds arrived on {}", to);
+            }
+        }
+        Command::Hosts { check_remote } => {
+            let mut hosts = discovery::list_remote_hosts(&home)?;
+            if check_remote {
+                discovery::check_remote_hosts(rt, &home, &mut hosts)?;
+            }
+            println!("Remote hosts of {}:", cli.chain);
+            for host in hosts {
+                println!(
+                    "  {:<12} polytone: {:<14} ics20: {:<14} live: {:<7} host: {} (deployed: {})",
+                    host.chain.to_string(),
+                    host.polytone_channel.unwrap_or_else(|| "-".into()),
+                    host.ics20_channel.unwrap_or_else(|| "-".into()),
+                    host.path_live
+                        .map(|live| live.to_string())
+                        .unwrap_or_else(|| "unknown".into()),
+                    host.ibc_host,
+                    host.host_deployed
+                        .map(|deployed| deployed.to_string())
+                        .unwrap_or_else(|| "unchecked".into()),
+                );
+            }
+        }
+        Command::Provision {
+            account,
+            hosts,
+            separate_txs,
+        } => {
+            let host_chains = hosts
+                .iter()
+                .map(|host| ChainName::from_string(host.clone()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let (interchain, _) = discovery::connect_with_hosts(rt, &cli.chain, &host_chains)?;
+            let home = interchain.chain(&cli.chain)?;
+            let account = load_account(&home, account)?;
+            let hosts: Vec<&str> = hosts.iter().map(String::as_str).collect();
+            let results = provision::provision(
+                &interchain,
+                &home,
+                &account,
+                &hosts,
+                !separate_txs,
+                &strategy,
+            )?;
+
+            println!("Remote accounts of {}:", account.id()?);
+            for result in results {
+                let status = match result.status {
+                    ProvisionStatus::AlreadyRegistered => "already registered".to_string(),
+                    ProvisionStatus::Registered => "registered".to_string(),
+                    ProvisionStatus::Failed(err) => format!("failed: {err}"),
+                    ProvisionStatus::Refused(err) => format!("refused: {err}"),
+                };
+                println!(
+                    "  {:<12} {:<66} {}",
+                    result.chain.to_string(),
+                    result.remote_proxy.unwrap_or_else(|| "-".into()),
+                    status
+                );
+            }
+        }
+        Command::Bootstrap {
+            account,
+            host,
+            deposit,
+            send,
+        } => {
+            let (interchain, _) = discovery::connect_with_hosts(
+                rt,
+                &cli.chain,
+                &[ChainName::from_string(host.clone())?],
+            )?;
+            let home = interchain.chain(&cli.chain)?;
+            let account = load_account(&home, account)?;
+            let controller = ics27::Controller::for_pair(&home, &account.id()?, &host)?;
+
+            let ibc_enabled = account.manager.is_module_installed(IBC_CLIENT_ID)?;
+            // Remote proxies can only be listed once IBC is enabled, before that there are none.
+            let registered: Vec<ChainName> = if ibc_enabled {
+                list_remote_proxies(&home, &account)?
+                    .into_iter()
+                    .map(|(chain, _)| chain)
+                    .collect()
+            } else {
+                vec![]
+            };
+            let (deposit, forward) = match deposit {
+                Some(deposit) => {
+                    let funds = HumanAmount::from_str(&deposit)?.resolve(&home)?;
+                    let forward = match (&funds.info, send) {
+                        (AssetInfo::Native(denom), true) => Some(coins(funds.amount.u128(), denom)),
+                        (_, true) => {
+                            anyhow::bail!("only native deposits can be forwarded to {host}")
+                        }
+                        (_, false) => None,
+                    };
+                    (Some(funds), forward)
+                }
+                None => (None, None),
+            };
+            let batch = match controller {
+                // The wallet registers the interchain account itself, the batch only deposits.
+                Some(controller) => {
+                    if forward.is_some() {
+                        anyhow::bail!("{host} is reached over ICS-27, deposits can't be forwarded");
+                    }
+                    discovery::ensure_host(&home, &host)?;
+                    let address = controller.register(&strategy)?;
+                    warn!("Interchain account on {} is {}", host, address);
+                    let host_chain = ChainName::from_string(host.clone())?;
+                    Batch::bootstrap(true, &[host_chain], &host, deposit, None)
+                }
+                None => {
+                    let batch = Batch::bootstrap(ibc_enabled, &registered, &host, deposit, forward);
+                    if batch.registers(&host) {
+                        discovery::ensure_host(&home, &host)?;
+                    }
+                    batch
+                }
+            };
+
+            let mut ledger = CostLedger::new();
+            let mut trace = LatencyTrace::new();
+            for (i, stage) in batch
+                .submit(&interchain, &home, &account, &strategy)?
+                .iter()
+                .enumerate()
+            {
+                let step = format!("bootstrap tx {}", i + 1);
+                match &stage.flow {
+                    Some(flow) => {
+                        trace.record_flow(step.clone(), Some(stage.submitted), flow);
+                        ledger.record_flow(step.clone(), flow);
+                        if let Err(err) = timeout::check(&account.id()?, &step, flow) {
+                            println!("{}", ledger);
+                            return Err(err.into());
+                        }
+                    }
+                    None => ledger.record(step, &cli.chain, &stage.tx),
+                }
+            }
+            println!("{}", ledger);
+            println!("{}", trace);
+        }
+        Command::RegisterRemote {
+            account,
+            host,
+            name,
+            description,
+            link,
+            namespace,
+            base_asset,
+            modules,
+        } => {
+            let (interchain, networks) = discovery::connect_with_hosts(
+                rt,
+                &cli.chain,
+                &[ChainName::from_string(host.clone())?],
+            )?;
+            let Some(remote_info) = networks.first() else {
+                anyhow::bail!("no known network for {host}");
+            };
+            let home = interchain.chain(&cli.chain)?;
+            let remote = interchain.chain(remote_info.chain_id)?;
+            let account = load_account(&home, account)?;
+
+            let mut builder = RemoteAccountBuilder::new(&host);
+            if let Some(name) = name {
+                builder = builder.name(name);
+            }
+            if let Some(description) = description {
+                builder = builder.description(description);
+            }
+            if let Some(link) = link {
+                builder = builder.link(link);
+            }
+            if let Some(namespace) = namespace {
+                builder = builder.namespace(namespace);
+            }
+            if let Some(base_asset) = base_asset {
+                builder = builder.base_asset(base_asset);
+            }
+            for module in modules {
+                builder = builder.install_module(ModuleInstallConfig::new(
+                    ModuleInfo::from_id_latest(&module)?,
+                    None,
+                ));
+            }
+            builder.build(&interchain, &home, &remote, &account, &strategy)?;
+            warn!(
+                "remote_proxies: {:?}",
+                list_remote_proxies(&home, &account)?
+            );
+        }
+        Command::SubAccounts { parent, action } => {
+            let parent = load_account(&home, parent)?;
+            match action {
+                SubAccountAction::List => {
+                    println!("Sub-accounts of {}:", parent.id()?);
+                    for sub in sub_accounts::list_sub_accounts(&home, &parent)? {
+                        let remotes: Vec<String> = sub
+                            .remote_proxies
+                            .iter()
+                            .map(|(chain, _)| chain.to_string())
+                            .collect();
+                        println!(
+                            "  {:<12} {:<24} balances: {:?} remotes: {:?}",
+                            sub.id.to_string(),
+                            sub.name,
+                            sub.balances,
+                            remotes
+                        );
+                    }
+                }
+                SubAccountAction::Reuse { name } => {
+                    let sub = sub_accounts::reuse_or_create(&home, &parent, &name)?;
+                    println!("{}", sub.id()?);
+                }
+                SubAccountAction::Sweep { id, ref cw20 }
+                | SubAccountAction::Decommission { id, ref cw20 } => {
+                    // Fail before connecting to every host.
+                    sub_accounts::load_sub_account(&home, &parent, id)?;
+                    let hosts: Vec<ChainName> = discovery::list_remote_hosts(&home)?
+                        .into_iter()
+                        .map(|host| host.chain)
+                        .collect();
+                    let (interchain, networks) =
+                        discovery::connect_with_hosts(rt, &cli.chain, &hosts)?;
+                    let home = interchain.chain(&cli.chain)?;
+                    let chain_ids: Vec<&str> = networks.iter().map(|info| info.chain_id).collect();
+                    let chains = sweep::chains_by_name(&interchain, &chain_ids)?;
+                    if matches!(action, SubAccountAction::Sweep { .. }) {
+                        let swept = sub_accounts::sweep(
+                            &interchain,
+                            &home,
+                            &parent,
+                            id,
+                            &chains,
+                            cw20,
+                            &strategy,
+                        )?;
+                        warn!("Swept {:?} to {}", swept, parent.id()?);
+                    } else {
+                        sub_accounts::decommission(
+                            &interchain,
+                            &home,
+                            &parent,
+                            id,
+                            &chains,
+                            cw20,
+                            &strategy,
+                        )?;
+                    }
+                }
+            }
+        }
+        Command::SweepRemotes {
+            account,
+            sub_accounts: include_sub_accounts,
+            execute,
+        } => {
+            let hosts: Vec<ChainName> = discovery::list_remote_hosts(&home)?
+                .into_iter()
+                .map(|host| host.chain)
+                .collect();
+            let (interchain, networks) = discovery::connect_with_hosts(rt, &cli.chain, &hosts)?;
+            let home = interchain.chain(&cli.chain)?;
+            let chain_ids: Vec<&str> = networks.iter().map(|info| info.chain_id).collect();
+            let chains = sweep::chains_by_name(&interchain, &chain_ids)?;
+
+            let account = load_account(&home, account)?;
+            let mut accounts = vec![account];
+            if include_sub_accounts {
+                for id in sub_accounts::sub_account_ids(&accounts[0])? {
+                    accounts.push(load_account(&home, id)?);
+                }
+            }
+
+            println!("Remote holdings:");
+            for account in &accounts {
+                for holding in sweep::scan(&home, account, &chains)? {
+                    println!(
+                        "  {:<12} {:<32} {}",
+                        holding.home_account.to_string(),
+                        holding.path_string(),
+                        holding
+                            .balances
+                            .map(|balances| format!("{:?}", balances))
+                            .unwrap_or_else(|| "not connected".into())
+                    );
+                }
+            }
+            if !execute {
+                println!("Dry run, pass --execute to bring these funds home");
+                return Ok(());
+            }
+            for account in &accounts {
+                let swept = sweep::sweep(&interchain, &home, account, &chains, &strategy)?;
+                warn!("Swept {} remote accounts of {}", swept.len(), account.id()?);
+            }
+        }
+        Command::Cw20 {
+            account,
+            host,
+            token,
+            action,
+        } => {
+            let (interchain, networks) = discovery::connect_with_hosts(
+                rt,
+                &cli.chain,
+                &[ChainName::from_string(host.clone())?],
+            )?;
+            let home = interchain.chain(&cli.chain)?;
+            let remote_info = networks
+                .first()
+                .ok_or_else(|| IcaaError::UnknownNetwork(host.clone()))?;
+            let remote = interchain.chain(remote_info.chain_id)?;
+            let account = load_account(&home, account)?;
+            let action = match action {
+                Cw20Command::Balance => {
+                    let balance = token::remote_balance(&home, &account, &remote, &token)?;
+                    println!("{} {} on {}", balance, token, host);
+                    return Ok(());
+                }
+                Cw20Command::Transfer { recipient, amount } => Cw20Action::Transfer {
+                    recipient,
+                    amount: AmountSpec::from_str(&amount)?,
+                },
+                Cw20Command::Send {
+                    contract,
+                    amount,
+                    msg,
+                } => Cw20Action::Send {
+                    contract,
+                    amount: AmountSpec::from_str(&amount)?,
+                    msg: to_json_binary(&serde_json::from_str::<serde_json::Value>(&msg)?)?,
+                },
+                Cw20Command::Allow { spender, amount } => Cw20Action::IncreaseAllowance {
+                    spender,
+                    amount: AmountSpec::from_str(&amount)?,
+                },
+                Cw20Command::Bridge {
+                    ics20,
+                    channel,
+                    remote_address,
+                    amount,
+                    timeout,
+                } => Cw20Action::Bridge {
+                    ics20,
+                    channel,
+                    remote_address,
+                    amount: AmountSpec::from_str(&amount)?,
+                    timeout,
+                },
+            };
+            token::execute(
+                &interchain,
+                &home,
+                &account,
+                &remote,
+                &token,
+                &action,
+                &strategy,
+            )?;
+            warn!("CW20 action executed on {}", host);
+        }
+        Command::Nft {
+            account,
+            host,
+            action,
+        } => {
+            let (interchain, networks) = discovery::connect_with_hosts(
+                rt,
+                &cli.chain,
+                &[ChainName::from_string(host.clone())?],
+            )?;
+            let home = interchain.chain(&cli.chain)?;
+            let remote_info = networks
+                .first()
+                .ok_or_else(|| IcaaError::UnknownNetwork(host.clone()))?;
+            let remote = interchain.chain(remote_info.chain_id)?;
+            let account = load_account(&home, account)?;
+            let (collection, action) = match action {
+                NftCommand::Owner {
+                    collection,
+                    token_id,
+                } => {
+                    let owner = nft::owner_of(&remote, &collection, &token_id)?;
+                    let info = nft::nft_info(&remote, &collection, &token_id)?;
+                    let proxy = remote::remote_proxy(&home, &account, &host)?;
+                    println!(
+                        "{} #{}: owned by {}{}",
+                        collection,
+                        token_id,
+                        owner,
+                        if owner == proxy {
+                            " (the remote account)"
+                        } else {
+                            ""
+                        }
+                    );
+                    println!("  uri      {}", info.token_uri.unwrap_or_default());
+                    println!("  metadata {}", info.extension.unwrap_or_default());
+                    return Ok(());
+                }
+                NftCommand::List { collections } => {
+                    let holdings = nft::holdings(&home, &account, &remote, &collections)?;
+                    println!("NFTs of {} on {}:", holdings.proxy, host);
+                    for (collection, tokens) in holdings.collections {
+                        println!("  {:<66} {:?}", collection, tokens);
+                    }
+                    return Ok(());
+                }
+                NftCommand::Mint {
+                    collection,
+                    token_id,
+                    owner,
+                    uri,
+                    metadata,
+                } => (
+                    collection,
+                    NftAction::Mint {
+                        token_id,
+                        owner,
+                        token_uri: uri,
+                        metadata: metadata
+                            .map(|metadata| serde_json::from_str(&metadata))
+                            .transpose()?,
+                    },
+                ),
+                NftCommand::Transfer {
+                    collection,
+                    token_id,
+                    recipient,
+                } => (
+                    collection,
+                    NftAction::Transfer {
+                        token_id,
+                        recipient,
+                    },
+                ),
+                NftCommand::Send {
+                    collection,
+                    token_id,
+                    contract,
+                    msg,
+                } => (
+                    collection,
+                    NftAction::Send {
+                        token_id,
+                        contract,
+                        msg: to_json_binary(&serde_json::from_str::<serde_json::Value>(&msg)?)?,
+                    },
+                ),
+            };
+            nft::execute(
+                &interchain,
+                &home,
+                &account,
+                &host,
+                &collection,
+                &[action],
+                &strategy,
+            )?;
+            warn!("NFT action executed on {}", host);
+        }
+        Command::Inspect { account } => {
+            let account = inspect::load(&home, &account.parse()?)?;
+            let hosts: Vec<ChainName> = discovery::list_remote_hosts(&home)?
+                .into_iter()
+                .map(|host| host.chain)
+                .collect();
+            let (interchain, networks) = discovery::connect_with_hosts(rt, &cli.chain, &hosts)?;
+            let chain_ids: Vec<&str> = networks.iter().map(|info| info.chain_id).collect();
+            let chains = sweep::chains_by_name(&interchain, &chain_ids)?;
+            println!("{}", inspect::inspect(&home, &account, &chains)?);
+        }
+        Command::Governance { account, action } => {
+            let account = load_account(&home, account)?;
+            match action {
+                GovernanceCommand::Show => {
+                    let hosts: Vec<ChainName> = discovery::list_remote_hosts(&home)?
+                        .into_iter()
+                        .map(|host| host.chain)
+                        .collect();
+                    let (interchain, networks) =
+                        discovery::connect_with_hosts(rt, &cli.chain, &hosts)?;
+                    let chain_ids: Vec<&str> = networks.iter().map(|info| info.chain_id).collect();
+                    let chains = sweep::chains_by_name(&interchain, &chain_ids)?;
+                    println!("{}", governance::view(&home, &account, &chains)?);
+                }
+                GovernanceCommand::Transfer { to, expiry_blocks } => {
+                    let new_owner = NewOwner::from(to);
+                    governance::transfer(&account, &new_owner, expiry_blocks)?;
+                    if let NewOwner::Multisig(address) = new_owner {
+                        println!(
+                            "The multisig {} must execute on {}:\n{}",
+                            address,
+                            account.manager.addr_str()?,
+                            serde_json::to_string(&governance::accept_msg())?
+                        );
+                    }
+                }
+                GovernanceCommand::Accept => governance::accept(&account)?,
+            }
+        }
+        Command::Recovery {
+            account: seq,
+            action,
+        } => {
+            let hosts: Vec<ChainName> = discovery::list_remote_hosts(&home)?
+                .into_iter()
+                .map(|host| host.chain)
+                .collect();
+            let (interchain, networks) = discovery::connect_with_hosts(rt, &cli.chain, &hosts)?;
+            let home = interchain.chain(&cli.chain)?;
+            let chain_ids: Vec<&str> = networks.iter().map(|info| info.chain_id).collect();
+            let chains = sweep::chains_by_name(&interchain, &chain_ids)?;
+            let account = load_account(&home, seq)?;
+            let (audit, ping) = match action {
+                RecoveryCommand::Audit { ping } => {
+                    (recovery::audit(&home, &account, &chains)?, ping)
+                }
+                RecoveryCommand::Start { to } => {
+                    let new_owner = NewOwner::from(to);
+                    if !recovery::recover(&home, &account, &chains, &new_owner)? {
+                        println!(
+                            "Transfer proposed, accept it with the new owner's signer: `icaa --signer <profile> recovery --account {} finish`",
+                            seq
+                        );
+                        if let NewOwner::Multisig(_) = new_owner {
+                            println!(
+                                "A multisig executes on {}:\n{}",
+                                account.manager.addr_str()?,
+                                serde_json::to_string(&governance::accept_msg())?
+                            );
+                        }
+                        return Ok(());
+                    }
+                    (recovery::audit(&home, &account, &chains)?, true)
+                }
+                RecoveryCommand::Finish { ping } => {
+                    (recovery::finish(&home, &account, &chains)?, ping)
+                }
+            };
+            println!("{}", audit);
+            if ping {
+                recovery::ping(&interchain, &home, &account, &audit, &strategy)?;
+            }
+            let unreachable = audit.unreachable();
+            if !unreachable.is_empty() {
+                warn!(
+                    "{} remote accounts can't be verified or reached",
+                    unreachable.len()
+                );
+            }
+        }
+        Command::Wait { txhash } => {
+            let hosts: Vec<ChainName> = discovery::list_remote_hosts(&home)?
+                .into_iter()
+                .map(|host| host.chain)
+                .collect();
+            let (interchain, _) = discovery::connect_with_hosts(rt, &cli.chain, &hosts)?;
+            let home = interchain.chain(&cli.chain)?;
+            wait::resume(&interchain, &home, &txhash, &strategy)?.done()?;
+            warn!("Packets of {} relayed", txhash);
+        }
+        Command::Pending => {
+            for pending in wait::pending()? {
+                println!(
+                    "{:<12} {:<66} {:>10}  {}",
+                    pending.chain_id, pending.txhash, pending.height, pending.step
+                );
+            }
+        }
+        Command::Keystore { .. } => unreachable!("handled before connecting"),
+        Command::Bench {
+            account,
+            host,
+            contract,
+            msg,
+            iterations,
+            out,
+        } => {
+            discovery::ensure_host(&home, &host)?;
+            let (interchain, networks) = discovery::connect_with_hosts(
+                rt,
+                &cli.chain,
+                &[ChainName::from_string(host.clone())?],
+            )?;
+            let home = interchain.chain(&cli.chain)?;
+            let remote_info = networks
+                .first()
+                .ok_or_else(|| IcaaError::UnknownNetwork(host.clone()))?;
+            let remote = interchain.chain(remote_info.chain_id)?;
+            let msgs = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract,
+                msg: to_json_binary(&serde_json::from_str::<serde_json::Value>(&msg)?)?,
+                funds: vec![],
+            })];
+
+            let direct = bench::Direct { remote };
+            let via_ibc = bench::ViaIbc {
+                interchain: &interchain,
+                home: home.clone(),
+                account: load_account(&home, account)?,
+                host_chain: host,
+                // Each run is timed until its outcome is known.
+                strategy: strategy.clone().detach(false),
+            };
+            let runs = bench::run(&[&direct, &via_ibc], &msgs, iterations);
+            bench::write_csv(Path::new(&format!("{out}.csv")), &runs)?;
+            bench::write_json(Path::new(&format!("{out}.json")), &runs)?;
+
+            println!(
+                "{:<40} {:>6} {:>9} {:>12} {:>10}  {:<24} {}",
+                "path", "runs", "failures", "latency ms", "gas", "wallet fee", "relayer fee"
+            );
+            for summary in bench::summarize(&runs) {
+                println!(
+                    "{:<40} {:>6} {:>9} {:>12} {:>10}  {:<24} {:?}",
+                    summary.path,
+                    summary.runs,
+                    summary.failures,
+                    summary.mean_latency_ms,
+                    summary.mean_gas_used,
+                    format!("{:?}", summary.mean_wallet_fees),
+                    summary.mean_relayer_fees
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn create_keystore(path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        anyhow::bail!("{} already exists", path.display());
+    }
+    let mnemonic = rpassword::prompt_password("Mnemonic: ")?;
+    let password = rpassword::prompt_password("Password: ")?;
+    if rpassword::prompt_password("Repeat password: ")? != password {
+        anyhow::bail!("passwords don't match");
+    }
+    std::fs::write(path, signer::encrypt_mnemonic(&mnemonic, &password)?)?;
+    warn!("Keystore written to {}", path.display());
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if cli.json_log {
+        std::env::set_var(run_log::JSON_LOG_ENV, "1");
+    }
+    runner::run(|rt| run(rt, cli))
+}
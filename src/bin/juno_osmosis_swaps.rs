@@ -8,21 +8,26 @@ use abstract_interface::{Abstract, AbstractAccount, ManagerExecFns};
 use abstract_std as abstract_core;
 use abstract_std::objects::AnsAsset;
 use abstract_std::{ibc_client, ibc_host::{HelperAction, HostAction}, manager, manager::ModuleInstallConfig, objects::{
-    chain_name::ChainName, gov_type::GovernanceDetails, module::ModuleInfo, AccountId,
+    chain_name::ChainName, module::ModuleInfo, AccountId,
     AssetEntry,
 }, PROXY, proxy};
-use cosmwasm_std::{coins, to_json_binary, Uint128};
+use cosmwasm_std::{to_json_binary, Uint128};
 use cw_asset::AssetInfo;
-use cw_orch_interchain::prelude::{ChannelCreationValidator, DaemonInterchainEnv, InterchainEnv};
+use cw_orch_interchain::prelude::InterchainEnv;
 use cw_orch::{
     contract::Deploy, daemon::networks::parse_network, daemon::queriers::Bank,
     environment::BankQuerier, prelude::*,
 };
 use log::warn;
-use pretty_env_logger::env_logger;
 use tokio::runtime::Runtime;
 
-use icaa_scripts::{press_enter_to_continue, ABSTRACT_DEX_ADAPTER_ID, IBC_CLIENT_ID, JUNO_1};
+use icaa_scripts::amount::{AmountSpec, HumanAmount};
+use icaa_scripts::remote::RemoteAccountBuilder;
+use icaa_scripts::wait::WaitStrategy;
+use icaa_scripts::{
+    press_enter_to_continue, runner, signer, timeout, transfer, ABSTRACT_DEX_ADAPTER_ID, IBC_CLIENT_ID,
+    JUNO_1,
+};
 
 const HOME_CHAIN_ID: &str = "juno-1";
 const HOME_CHAIN_NAME: &str = "juno";
@@ -34,17 +39,12 @@ const REMOTE_CHAIN_BASE_ASSET: &str = "osmosis>osmo";
 const HOME_DEX_NAME: &str = "wyndex";
 const REMOTE_DEX_NAME: &str = "osmosis";
 
-fn icaa_demo() -> anyhow::Result<()> {
-    let rt = Runtime::new()?;
+fn icaa_demo(rt: &Runtime) -> anyhow::Result<()> {
 
     // Setup interchain environment
     let home_chain_info = JUNO_1; // let home_chain_info = parse_network(HOME_CHAIN_ID).unwrap();
     let remote_chain_info = parse_network(REMOTE_CHAIN_ID).unwrap();
-    let interchain = DaemonInterchainEnv::new(
-        rt.handle(),
-        vec![(home_chain_info.clone(), None), (remote_chain_info, None)],
-        &ChannelCreationValidator,
-    )?;
+    let interchain = signer::connect_chains(rt, vec![home_chain_info.clone(), remote_chain_info])?;
 
     // Home chain is where all transactions originate
     let home = interchain.chain(HOME_CHAIN_ID)?;
@@ -109,30 +109,14 @@ fn icaa_demo() -> anyhow::Result<()> {
     {
         warn!("Registering remote account on {}", REMOTE_CHAIN_NAME);
 
-        // @feedback on below: we should be able to customize the remote account, should be named: `create_remote_account`, ensure doc comment specifies what happens if exists
-        // let remote_acc_tx = home_acc.register_remote_account(REMOTE_CHAIN_NAME)?;
-
-        // thought: maybe we could do with account builder (like sub_account method)
-        // Ex: let remote_acc = home_client.account_builder().remote_account(home_account_client)
-
-        // @feedback - wrong doc comment, also should be named `create_remote_account`
-        // parent_account_client.create_ibc_account()
-        let remote_acc_tx = home_account_client.execute(
-            vec![&abstract_core::proxy::ExecuteMsg::IbcAction {
-                msg: abstract_core::ibc_client::ExecuteMsg::Register {
-                    host_chain: REMOTE_CHAIN_NAME.into(),
-                    base_asset: Some(AssetEntry::from(REMOTE_CHAIN_BASE_ASSET)),
-                    namespace: None,
-                    install_modules: vec![ModuleInstallConfig::new(
-                        ModuleInfo::from_id_latest(ABSTRACT_DEX_ADAPTER_ID)?,
-                        None,
-                    )],
-                },
-            }.into()?],
-            &[],
-        )?;
-        // @feedback chain id or chain name?
-        interchain.wait_ibc(&HOME_CHAIN_ID, remote_acc_tx)?;
+        RemoteAccountBuilder::new(REMOTE_CHAIN_NAME)
+            .name("ICAA PL Test")
+            .base_asset(REMOTE_CHAIN_BASE_ASSET)
+            .install_module(ModuleInstallConfig::new(
+                ModuleInfo::from_id_latest(ABSTRACT_DEX_ADAPTER_ID)?,
+                None,
+            ))
+            .build(&interchain, &home, &remote, &home_acc, &WaitStrategy::default())?;
 
         remote_proxies = icaa_scripts::list_remote_proxies(&home, &home_acc)?;
         warn!("remote_proxies: {:?}", remote_proxies);
@@ -153,18 +137,11 @@ fn icaa_demo() -> anyhow::Result<()> {
     let remote_balances = get_remote_balances(&remote, &home_acc)?;
     warn!("Remote balances before sending: {:?}", remote_balances);
 
-    let mut home_base_denom_balance = home_account_client.query_balance(home_denom)?;
+    let home_base_denom_balance = home_account_client.query_balance(home_denom)?;
     if home_base_denom_balance.is_zero() {
         warn!("Sending some funds from wallet to account.");
-        // @feedback make it easier to send funds from wallet?
-        //  - maybe acc_client.deposit() method
-        let _bank_send_tx = rt.block_on(home.daemon.sender.bank_send(
-            // @feedback: home_account_client.address() to get the address of the proxy?
-            home_account_client.proxy()?.as_str(),
-            coins(500, home_denom),
-        ))?;
-
-        home_base_denom_balance = home_account_client.query_balance(home_denom)?;
+        let funds = HumanAmount::new("0.0005", HOME_CHAIN_BASE_ASSET)?.resolve(&home)?;
+        let _deposit_tx = transfer::deposit(&home, &home_acc, &funds)?;
     }
 
     press_enter_to_continue();
@@ -174,17 +151,22 @@ fn icaa_demo() -> anyhow::Result<()> {
         "Sending funds from {} to {}.",
         HOME_CHAIN_ID, REMOTE_CHAIN_NAME
     );
-    // @feedback: should be able to send_funds_to_remote
-    let send_funds_tx = home_acc.manager.execute_on_module(
-        PROXY,
-        abstract_core::proxy::ExecuteMsg::IbcAction {
-            msg: abstract_core::ibc_client::ExecuteMsg::SendFunds {
-                host_chain: REMOTE_CHAIN_NAME.into(),
-                funds: coins(home_base_denom_balance.u128(), home_denom),
-            },
-        },
+    let send_funds_tx = transfer::send_funds(
+        &home,
+        &home_acc,
+        REMOTE_CHAIN_NAME,
+        HOME_CHAIN_BASE_ASSET,
+        &AmountSpec::All,
+        Uint128::zero(),
+    )?;
+    timeout::wait_checked(
+        &interchain,
+        &home,
+        &home_acc.id()?,
+        "send funds",
+        send_funds_tx,
+        &WaitStrategy::default(),
     )?;
-    interchain.wait_ibc(&HOME_CHAIN_ID, send_funds_tx)?;
 
     // Check both balances
     let home_balance = home_account_client.query_balance(home_denom)?;
@@ -225,7 +207,14 @@ fn icaa_demo() -> anyhow::Result<()> {
         )?,
         // None,
     )?;
-    interchain.wait_ibc(&HOME_CHAIN_ID, swap_tx)?;
+    timeout::wait_checked(
+        &interchain,
+        &home,
+        &home_acc.id()?,
+        "remote swap",
+        swap_tx,
+        &WaitStrategy::default(),
+    )?;
 
     warn!(
         "Successfully swapped assets using {}'s dex on {}!",
@@ -276,7 +265,14 @@ fn icaa_demo() -> anyhow::Result<()> {
         },
     )?;
 
-    interchain.wait_ibc(&HOME_CHAIN_ID, send_funds_tx)?;
+    timeout::wait_checked(
+        &interchain,
+        &home,
+        &home_acc.id()?,
+        "request funds back",
+        send_funds_tx,
+        &WaitStrategy::default(),
+    )?;
 
     let home_balances = home_account_client.query_balances()?;
     warn!("Home balances after receiving back: {:?}", home_balances);
@@ -403,27 +399,7 @@ fn get_remote_balance(
 }
 
 fn main() {
-    dotenv().ok();
-    env_logger::init();
-
-    use dotenv::dotenv;
-
-    if let Err(ref err) = icaa_demo() {
-        log::error!("{}", err);
-        err.chain()
-            .skip(1)
-            .for_each(|cause| log::error!("because: {}", cause));
-
-        // The backtrace is not always generated. Try to run this example
-
-        // with `$env:RUST_BACKTRACE=1`.
-
-        //    if let Some(backtrace) = err.un.backtrace() {
-        //        log::debug!("backtrace: {:?}", backtrace);
-        //    }
-
-        ::std::process::exit(1);
-    }
+    runner::run(icaa_demo)
 }
 
 /*
@@ -10,14 +10,18 @@ use abstract_core::objects::{AccountId, AssetEntry};
 
 use abstract_core::PROXY;
 use abstract_interface::{Abstract, AbstractAccount, ManagerExecFns};
-use cosmwasm_std::{coins, Uint128};
+use cosmwasm_std::Uint128;
 use cw_asset::AssetInfo;
 use cw_orch::daemon::networks::parse_network;
 use cw_orch::environment::BankQuerier;
-use cw_orch_interchain::prelude::{ChannelCreationValidator, DaemonInterchainEnv, InterchainEnv};
 use cw_orch::{contract::Deploy, prelude::*};
-use icaa_scripts::{press_enter_to_continue, IBC_CLIENT_ID, JUNO_1};
-use pretty_env_logger::env_logger;
+use cw_orch_interchain::prelude::InterchainEnv;
+use icaa_scripts::amount::{AmountSpec, HumanAmount};
+use icaa_scripts::remote::ensure_remote_account;
+use icaa_scripts::wait::WaitStrategy;
+use icaa_scripts::{
+    press_enter_to_continue, runner, signer, timeout, transfer, IBC_CLIENT_ID, JUNO_1,
+};
 use tokio::runtime::Runtime;
 
 use log::warn;
@@ -26,18 +30,14 @@ const HOME_CHAIN_ID: &str = "juno-1";
 const HOME_CHAIN_NAME: &str = "juno";
 const REMOTE_CHAIN_ID: &str = "archway-1";
 const REMOTE_CHAIN_NAME: &str = "archway";
+/// How many times a timed out transfer or request is submitted again.
+const RETRIES: u32 = 2;
 
-fn deploy() -> anyhow::Result<()> {
-    let rt = Runtime::new()?;
-
+fn deploy(rt: &Runtime) -> anyhow::Result<()> {
     // Setup interchain environment
     let home_chain_info = JUNO_1; // let home_chain_info = parse_network(HOME_CHAIN_ID).unwrap();
     let remote_chain_info = parse_network(REMOTE_CHAIN_ID).unwrap();
-    let interchain = DaemonInterchainEnv::new(
-        rt.handle(),
-        vec![(home_chain_info.clone(), None), (remote_chain_info, None)],
-        &ChannelCreationValidator,
-    )?;
+    let interchain = signer::connect_chains(rt, vec![home_chain_info.clone(), remote_chain_info])?;
 
     // Home chain is where all transactions originate
     let home = interchain.chain(HOME_CHAIN_ID)?;
@@ -79,27 +79,15 @@ fn deploy() -> anyhow::Result<()> {
         warn!("IBC is already enabled on {}!", HOME_CHAIN_NAME);
     }
 
-    // could sanity check that osmosis is an available host
-    // let remote_hosts = ibc_client.list_remote_hosts()?.hosts;
-    // warn!("hosts: {:?}", remote_hosts);
-
-    // CHeck for and register remote account on osmosis
-    let mut remote_proxies = icaa_scripts::list_remote_proxies(&home, &home_acc)?;
-
-    if !remote_proxies
-        .iter()
-        .any(|(chain, _)| chain == &ChainName::from_str(REMOTE_CHAIN_NAME).unwrap())
-    {
-        warn!("Registering remote account on {}", REMOTE_CHAIN_NAME);
-        let remote_acc_tx = home_acc.register_remote_account(REMOTE_CHAIN_NAME)?;
-        // @feedback chain id or chain name?
-        interchain.wait_ibc(HOME_CHAIN_ID, remote_acc_tx)?;
-
-        remote_proxies = icaa_scripts::list_remote_proxies(&home, &home_acc)?;
-        warn!("remote_proxies: {:?}", remote_proxies);
-    } else {
-        warn!("{} already registered", REMOTE_CHAIN_NAME);
-    }
+    // Check for and register remote account on archway, refusing if archway isn't a host
+    let remote_proxies = ensure_remote_account(
+        &interchain,
+        &home,
+        &home_acc,
+        REMOTE_CHAIN_NAME,
+        &WaitStrategy::default(),
+    )?;
+    warn!("remote_proxies: {:?}", remote_proxies);
 
     press_enter_to_continue();
     let remote_balance = get_remote_balance(&remote, &home_acc)?;
@@ -111,12 +99,8 @@ fn deploy() -> anyhow::Result<()> {
     warn!("Home balance is: {}", home_balance);
     if home_balance.is_zero() {
         warn!("Sending some funds from wallet to account.");
-        // @feedback make it easier to send funds from wallet?
-        //  - maybe a acc_client.deposit() method
-        rt.block_on(home.daemon.sender.bank_send(
-            home_account_client.proxy()?.as_str(),
-            coins(500, home_denom),
-        ))?;
+        let funds = HumanAmount::new("0.0005", "juno")?.resolve(&home)?;
+        transfer::deposit(&home, &home_acc, &funds)?;
     }
 
     // Send funds to the remote account
@@ -124,16 +108,25 @@ fn deploy() -> anyhow::Result<()> {
         "Sending funds from {} to {}.",
         HOME_CHAIN_ID, REMOTE_CHAIN_NAME
     );
-    let send_funds_tx = home_acc.manager.execute_on_module(
-        PROXY,
-        abstract_core::proxy::ExecuteMsg::IbcAction {
-            msg: abstract_core::ibc_client::ExecuteMsg::SendFunds {
-                host_chain: REMOTE_CHAIN_NAME.into(),
-                funds: coins(home_balance.u128(), home_denom),
-            },
+    let home_acc_id = home_acc.id()?;
+    timeout::submit_with_retry(
+        &interchain,
+        &home,
+        &home_acc_id,
+        "send funds",
+        RETRIES,
+        &WaitStrategy::default(),
+        || {
+            transfer::send_funds(
+                &home,
+                &home_acc,
+                REMOTE_CHAIN_NAME,
+                "juno",
+                &AmountSpec::All,
+                Uint128::zero(),
+            )
         },
     )?;
-    interchain.wait_ibc(HOME_CHAIN_ID, send_funds_tx)?;
 
     // Check both balances
     let home_balance = home_account_client.query_balance(home_denom)?;
@@ -146,18 +139,26 @@ fn deploy() -> anyhow::Result<()> {
 
     // send funds back
     warn!("Requesting all funds back");
-    let send_funds_tx = home_acc.manager.execute_on_module(
-        PROXY,
-        abstract_core::proxy::ExecuteMsg::IbcAction {
-            msg: abstract_core::ibc_client::ExecuteMsg::RemoteAction {
-                host_chain: REMOTE_CHAIN_NAME.into(),
-                action: HostAction::Helpers(HelperAction::SendAllBack),
-            },
+    timeout::submit_with_retry(
+        &interchain,
+        &home,
+        &home_acc_id,
+        "request funds back",
+        RETRIES,
+        &WaitStrategy::default(),
+        || {
+            Ok(home_acc.manager.execute_on_module(
+                PROXY,
+                abstract_core::proxy::ExecuteMsg::IbcAction {
+                    msg: abstract_core::ibc_client::ExecuteMsg::RemoteAction {
+                        host_chain: REMOTE_CHAIN_NAME.into(),
+                        action: HostAction::Helpers(HelperAction::SendAllBack),
+                    },
+                },
+            )?)
         },
     )?;
 
-    interchain.wait_ibc(HOME_CHAIN_ID, send_funds_tx)?;
-
     let home_balance = home_account_client.query_balance(home_denom)?;
     warn!("Home balance after receiving back: {:?}", home_balance);
 
@@ -203,27 +204,7 @@ fn get_remote_balance(
 }
 
 fn main() {
-    dotenv().ok();
-    env_logger::init();
-
-    use dotenv::dotenv;
-
-    if let Err(ref err) = deploy() {
-        log::error!("{}", err);
-        err.chain()
-            .skip(1)
-            .for_each(|cause| log::error!("because: {}", cause));
-
-        // The backtrace is not always generated. Try to run this example
-
-        // with `$env:RUST_BACKTRACE=1`.
-
-        //    if let Some(backtrace) = err.un.backtrace() {
-        //        log::debug!("backtrace: {:?}", backtrace);
-        //    }
-
-        ::std::process::exit(1);
-    }
+    runner::run(deploy)
 }
 
 /*
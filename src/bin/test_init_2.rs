@@ -9,9 +9,8 @@ use abstract_core::objects::{AccountId};
 use abstract_interface::{Abstract};
 use cw_orch::daemon::networks::parse_network;
 use cw_orch::{contract::Deploy, prelude::*};
-use cw_orch_interchain::prelude::{ChannelCreationValidator, DaemonInterchainEnv, InterchainEnv};
-use icaa_scripts::{press_enter_to_continue};
-use pretty_env_logger::env_logger;
+use cw_orch_interchain::prelude::InterchainEnv;
+use icaa_scripts::{press_enter_to_continue, runner, signer};
 use tokio::runtime::Runtime;
 
 
@@ -20,17 +19,12 @@ const HOME_CHAIN_NAME: &str = "osmosis";
 const REMOTE_CHAIN_ID: &str = "archway-1";
 const REMOTE_CHAIN_NAME: &str = "archway";
 
-fn deploy() -> anyhow::Result<()> {
-    let rt = Runtime::new()?;
+fn deploy(rt: &Runtime) -> anyhow::Result<()> {
 
     // Setup interchain environment
     let home_chain_info = parse_network(HOME_CHAIN_ID).unwrap();
     let remote_chain_info = parse_network(REMOTE_CHAIN_ID).unwrap();
-    let interchain = DaemonInterchainEnv::new(
-        rt.handle(),
-        vec![(home_chain_info.clone(), None), (remote_chain_info, None)],
-        &ChannelCreationValidator,
-    )?;
+    let interchain = signer::connect_chains(rt, vec![home_chain_info.clone(), remote_chain_info])?;
 
     // Home chain is where all transactions originate
     let home = interchain.chain(HOME_CHAIN_ID)?;
@@ -68,27 +62,7 @@ fn deploy() -> anyhow::Result<()> {
 }
 
 fn main() {
-    dotenv().ok();
-    env_logger::init();
-
-    use dotenv::dotenv;
-
-    if let Err(ref err) = deploy() {
-        log::error!("{}", err);
-        err.chain()
-            .skip(1)
-            .for_each(|cause| log::error!("because: {}", cause));
-
-        // The backtrace is not always generated. Try to run this example
-
-        // with `$env:RUST_BACKTRACE=1`.
-
-        //    if let Some(backtrace) = err.un.backtrace() {
-        //        log::debug!("backtrace: {:?}", backtrace);
-        //    }
-
-        ::std::process::exit(1);
-    }
+    runner::run(deploy)
 }
 
 /*
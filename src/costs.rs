@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use cosmwasm_std::{Coin, Uint128};
+use cw_orch::daemon::{CosmTxResponse, Daemon};
+use cw_orch::prelude::*;
+use cw_orch_interchain::types::{IbcPacketOutcome, NestedPacketsFlow};
+
+/// Who paid for a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Payer {
+    /// Our own wallet, signing on the home chain.
+    Wallet,
+    /// A relayer delivering packets or acks.
+    Relayer,
+}
+
+impl fmt::Display for Payer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Payer::Wallet => write!(f, "wallet"),
+            Payer::Relayer => write!(f, "relayer"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TxCost {
+    pub chain_id: String,
+    pub txhash: String,
+    pub payer: Payer,
+    pub gas_wanted: u64,
+    pub gas_used: u64,
+    /// Fee as reported by the `tx` event, empty when the node didn't return it.
+    pub fee: Vec<Coin>,
+}
+
+impl TxCost {
+    pub fn new(chain_id: impl Into<String>, payer: Payer, resp: &CosmTxResponse) -> Self {
+        Self {
+            chain_id: chain_id.into(),
+            txhash: resp.txhash.clone(),
+            payer,
+            gas_wanted: resp.gas_wanted,
+            gas_used: resp.gas_used,
+            fee: resp
+                .event_attr_value("tx", "fee")
+                .map(|fee| parse_coins(&fee))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Whether `other` is the same transaction.
+    fn is(&self, other: &TxCost) -> bool {
+        self.chain_id == other.chain_id && self.txhash == other.txhash
+    }
+}
+
+/// Parse a coin list like `1234ujuno,5ibc/ABC`.
+pub fn parse_coins(s: &str) -> Vec<Coin> {
+    s.split(',')
+        .filter_map(|coin| {
+            let coin = coin.trim();
+            let split = coin.find(|c: char| !c.is_ascii_digit())?;
+            let (amount, denom) = coin.split_at(split);
+            Some(Coin {
+                denom: denom.to_string(),
+                amount: Uint128::new(amount.parse().ok()?),
+            })
+        })
+        .collect()
+}
+
+/// The costs of one step of a flow: the home transaction and everything relayed because of it.
+#[derive(Debug, Clone)]
+pub struct StepCost {
+    pub step: String,
+    pub txs: Vec<TxCost>,
+}
+
+/// Records gas and fees of every step of a flow.
+#[derive(Debug, Clone, Default)]
+pub struct CostLedger {
+    pub steps: Vec<StepCost>,
+}
+
+impl CostLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a transaction without IBC packets.
+    pub fn record(&mut self, step: impl Into<String>, chain_id: &str, resp: &CosmTxResponse) {
+        self.steps.push(StepCost {
+            step: step.into(),
+            txs: vec![TxCost::new(chain_id, Payer::Wallet, resp)],
+        });
+    }
+
+    /// Record the home transaction of `flow` and every relayer transaction that followed it.
+    ///
+    /// A relayer transaction already recorded by an earlier step, e.g. one delivering the packets
+    /// of several steps, stays counted in that step only.
+    pub fn record_flow(&mut self, step: impl Into<String>, flow: &NestedPacketsFlow<Daemon>) {
+        let mut txs = vec![TxCost::new(
+            flow.tx_id.chain_id.clone(),
+            Payer::Wallet,
+            &flow.tx_id.response,
+        )];
+        relayer_costs(flow, &mut txs);
+        self.push_step(step.into(), txs);
+    }
+
+    /// Add a step, leaving out the relayer txs an earlier step already counts.
+    fn push_step(&mut self, step: String, mut txs: Vec<TxCost>) {
+        txs.retain(|tx| tx.payer == Payer::Wallet || !self.contains(tx));
+        self.steps.push(StepCost { step, txs });
+    }
+
+    fn contains(&self, tx: &TxCost) -> bool {
+        self.steps
+            .iter()
+            .flat_map(|step| &step.txs)
+            .any(|seen| seen.is(tx))
+    }
+
+    /// Totals per payer, chain and denom, plus gas used per payer and chain.
+    pub fn summary(&self) -> CostSummary {
+        let mut summary = CostSummary::default();
+        for tx in self.steps.iter().flat_map(|step| &step.txs) {
+            *summary
+                .gas_used
+                .entry((tx.payer, tx.chain_id.clone()))
+                .or_default() += tx.gas_used;
+            for coin in &tx.fee {
+                *summary
+                    .fees
+                    .entry((tx.payer, tx.chain_id.clone(), coin.denom.clone()))
+                    .or_default() += coin.amount;
+            }
+        }
+        summary
+    }
+}
+
+/// Relayers deliver several packets per transaction, so each relayer transaction is counted once.
+fn relayer_costs(flow: &NestedPacketsFlow<Daemon>, txs: &mut Vec<TxCost>) {
+    for packet in &flow.packets {
+        match &packet.outcome {
+            IbcPacketOutcome::Timeout { timeout_tx } => push_once(
+                txs,
+                TxCost::new(
+                    timeout_tx.tx_id.chain_id.clone(),
+                    Payer::Relayer,
+                    &timeout_tx.tx_id.response,
+                ),
+            ),
+            IbcPacketOutcome::Success {
+                receive_tx, ack_tx, ..
+            } => {
+                push_once(
+                    txs,
+                    TxCost::new(
+                        receive_tx.tx_id.chain_id.clone(),
+                        Payer::Relayer,
+                        &receive_tx.tx_id.response,
+                    ),
+                );
+                push_once(
+                    txs,
+                    TxCost::new(
+                        ack_tx.tx_id.chain_id.clone(),
+                        Payer::Relayer,
+                        &ack_tx.tx_id.response,
+                    ),
+                );
+                relayer_costs(receive_tx, txs);
+            }
+        }
+    }
+}
+
+fn push_once(txs: &mut Vec<TxCost>, tx: TxCost) {
+    if !txs.iter().any(|seen| seen.is(&tx)) {
+        txs.push(tx);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CostSummary {
+    pub gas_used: BTreeMap<(Payer, String), u64>,
+    pub fees: BTreeMap<(Payer, String, String), Uint128>,
+}
+
+impl fmt::Display for CostSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Gas used:")?;
+        for ((payer, chain_id), gas) in &self.gas_used {
+            writeln!(f, "  {:<8} {:<16} {}", payer, chain_id, gas)?;
+        }
+        writeln!(f, "Fees paid:")?;
+        for ((payer, chain_id, denom), amount) in &self.fees {
+            writeln!(f, "  {:<8} {:<16} {}{}", payer, chain_id, amount, denom)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CostLedger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{}:", step.step)?;
+            for tx in &step.txs {
+                writeln!(
+                    f,
+                    "  {:<8} {:<16} gas {:>8}/{:<8} fee {:?} ({})",
+                    tx.payer, tx.chain_id, tx.gas_used, tx.gas_wanted, tx.fee, tx.txhash
+                )?;
+            }
+        }
+        write!(f, "{}", self.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::coins;
+
+    use super::*;
+
+    fn relayer_tx(chain_id: &str, txhash: &str) -> TxCost {
+        TxCost {
+            chain_id: chain_id.to_string(),
+            txhash: txhash.to_string(),
+            payer: Payer::Relayer,
+            gas_wanted: 200_000,
+            gas_used: 150_000,
+            fee: coins(5_000, "uosmo"),
+        }
+    }
+
+    #[test]
+    fn relayer_txs_shared_by_steps_count_in_the_first_one() {
+        let mut ledger = CostLedger::new();
+        ledger.push_step(
+            "send funds".to_string(),
+            vec![
+                relayer_tx("osmosis-1", "AAAA"),
+                relayer_tx("juno-1", "BBBB"),
+            ],
+        );
+        ledger.push_step(
+            "send back".to_string(),
+            vec![
+                relayer_tx("osmosis-1", "AAAA"),
+                relayer_tx("osmosis-1", "CCCC"),
+            ],
+        );
+
+        let hashes: Vec<Vec<&str>> = ledger
+            .steps
+            .iter()
+            .map(|step| step.txs.iter().map(|tx| tx.txhash.as_str()).collect())
+            .collect();
+        assert_eq!(hashes, [vec!["AAAA", "BBBB"], vec!["CCCC"]]);
+        assert_eq!(
+            ledger.summary().gas_used[&(Payer::Relayer, "osmosis-1".to_string())],
+            300_000
+        );
+    }
+
+    #[test]
+    fn relayer_txs_delivering_several_packets_count_once() {
+        let mut txs = vec![];
+        push_once(&mut txs, relayer_tx("osmosis-1", "AAAA"));
+        push_once(&mut txs, relayer_tx("osmosis-1", "AAAA"));
+        push_once(&mut txs, relayer_tx("osmosis-1", "BBBB"));
+        push_once(&mut txs, relayer_tx("juno-1", "AAAA"));
+        let ledger = CostLedger {
+            steps: vec![StepCost {
+                step: "send funds".to_string(),
+                txs,
+            }],
+        };
+
+        let summary = ledger.summary();
+        assert_eq!(
+            summary.gas_used[&(Payer::Relayer, "osmosis-1".to_string())],
+            300_000
+        );
+        assert_eq!(
+            summary.fees[&(Payer::Relayer, "osmosis-1".to_string(), "uosmo".to_string())],
+            Uint128::new(10_000)
+        );
+        assert_eq!(
+            summary.gas_used[&(Payer::Relayer, "juno-1".to_string())],
+            150_000
+        );
+    }
+}
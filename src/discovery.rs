@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use abstract_interface::Abstract;
+use abstract_std::ans_host::QueryMsgFns as AnsQueryFns;
+use abstract_std::ibc_client::QueryMsgFns;
+use abstract_std::objects::chain_name::ChainName;
+use abstract_std::objects::ChannelEntry;
+use abstract_std::ICS20;
+use cosmwasm_std::Addr;
+use cw_orch::daemon::networks::SUPPORTED_NETWORKS;
+use cw_orch::daemon::queriers::Ibc;
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::{ChainInfo, ChainKind, WasmQuerier};
+use cw_orch::prelude::*;
+use cw_orch_interchain::prelude::{DaemonInterchainEnv, InterchainEnv};
+use log::warn;
+use serde::Serialize;
+use tokio::runtime::Runtime;
+
+use crate::error::{IcaaError, IcaaResult};
+use crate::ics27::{Backend, Backends};
+use crate::{chain_id, chain_info, chain_name, connect};
+
+/// `ibc.core.channel.v1.State::Open`
+const CHANNEL_STATE_OPEN: i32 = 3;
+/// `ibc.core.connection.v1.State::Open`
+const CONNECTION_STATE_OPEN: i32 = 3;
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PolytoneNoteQuery {
+    ActiveChannel {},
+}
+
+/// A remote host as seen from the home chain's Abstract IBC client.
+#[derive(Debug, Clone)]
+pub struct RemoteHost {
+    pub chain: ChainName,
+    pub ibc_host: String,
+    pub polytone_note: Addr,
+    pub polytone_channel: Option<String>,
+    pub ics20_channel: Option<String>,
+    /// Whether the polytone channel is open on the home side, `None` if the note couldn't tell.
+    pub path_live: Option<bool>,
+    /// Whether the `ibc-host` contract exists on the remote chain, `None` if it wasn't checked.
+    pub host_deployed: Option<bool>,
+}
+
+/// Remote hosts listed by [`ensure_host`] this run, by home chain id.
+static HOSTS: OnceLock<Mutex<HashMap<String, Vec<RemoteHost>>>> = OnceLock::new();
+
+/// List every remote host registered on the home chain's IBC client with its channels.
+///
+/// A host whose polytone note or channel fails to answer is listed with an unknown path.
+pub fn list_remote_hosts(home: &Daemon) -> IcaaResult<Vec<RemoteHost>> {
+    let abstr = Abstract::load_from(home.clone())?;
+    let counterparts = abstr.ibc.client.list_ibc_infrastructures()?.counterparts;
+
+    counterparts
+        .into_iter()
+        .map(|(chain, infra)| {
+            let active_channel: Result<Option<String>, _> = home
+                .wasm_querier()
+                .smart_query(&infra.polytone_note, &PolytoneNoteQuery::ActiveChannel {});
+            let (polytone_channel, path_live) = match active_channel {
+                Ok(Some(channel)) => {
+                    let port_id = format!("wasm.{}", infra.polytone_note);
+                    let open = match channel_open(home, &port_id, &channel) {
+                        Ok(open) => Some(open),
+                        Err(e) => {
+                            warn!("Can't query channel {} of {}: {}", channel, chain, e);
+                            None
+                        }
+                    };
+                    (Some(channel), open)
+                }
+                Ok(None) => (None, Some(false)),
+                Err(e) => {
+                    warn!(
+                        "Polytone note {} for {} didn't return its channel: {}",
+                        infra.polytone_note, chain, e
+                    );
+                    (None, None)
+                }
+            };
+            let ics20_channel = abstr
+                .ans_host
+                .channels(vec![ChannelEntry {
+                    connected_chain: chain.clone(),
+                    protocol: ICS20.to_string(),
+                }])
+                .ok()
+                .and_then(|resp| resp.channels.into_iter().next())
+                .map(|(_, channel)| channel);
+            Ok(RemoteHost {
+                chain,
+                ibc_host: infra.remote_abstract_host,
+                polytone_note: infra.polytone_note,
+                polytone_channel,
+                ics20_channel,
+                path_live,
+                host_deployed: None,
+            })
+        })
+        .collect()
+}
+
+fn channel_open(chain: &Daemon, port_id: &str, channel_id: &str) -> IcaaResult<bool> {
+    let channel = chain
+        .rt_handle
+        .block_on(Ibc::new(chain)._channel(port_id, channel_id))?;
+    Ok(channel.state == CHANNEL_STATE_OPEN)
+}
+
+fn connection_open(chain: &Daemon, connection_id: &str) -> IcaaResult<bool> {
+    let connection = chain
+        .rt_handle
+        .block_on(Ibc::new(chain)._connection_end(connection_id))?;
+    Ok(connection.is_some_and(|connection| connection.state == CONNECTION_STATE_OPEN))
+}
+
+/// Find the network for an Abstract chain name, on the same kind of network as the home chain.
+pub fn network_by_name(name: &ChainName, kind: ChainKind) -> Option<ChainInfo> {
+    SUPPORTED_NETWORKS
+        .iter()
+        .find(|info| info.kind == kind && &ChainName::from_chain_id(info.chain_id) == name)
+        .cloned()
+}
+
+/// Connect to the home chain and every host chain we know a network for, by Abstract chain name.
+///
+/// Hosts without a known network are skipped with a warning.
+pub fn connect_with_hosts(
+    rt: &Runtime,
+    home_chain_id: &str,
+    host_chains: &[ChainName],
+) -> IcaaResult<(DaemonInterchainEnv, Vec<ChainInfo>)> {
+    let kind = chain_info(home_chain_id)?.kind;
+    let networks: Vec<ChainInfo> = host_chains
+        .iter()
+        .filter_map(|host| {
+            let network = network_by_name(host, kind.clone());
+            if network.is_none() {
+                warn!("No known network for {}, skipping it", host);
+            }
+            network
+        })
+        .collect();
+    let remote_ids: Vec<&str> = networks.iter().map(|info| info.chain_id).collect();
+    Ok((connect(rt, home_chain_id, &remote_ids)?, networks))
+}
+
+/// Connect to every host's chain and check that its `ibc-host` contract is deployed.
+pub fn check_remote_hosts(rt: &Runtime, home: &Daemon, hosts: &mut [RemoteHost]) -> IcaaResult<()> {
+    let host_chains: Vec<ChainName> = hosts.iter().map(|host| host.chain.clone()).collect();
+    let (interchain, networks) = connect_with_hosts(rt, &chain_id(home), &host_chains)?;
+
+    for host in hosts.iter_mut() {
+        let Some(info) = networks
+            .iter()
+            .find(|info| ChainName::from_chain_id(info.chain_id) == host.chain)
+        else {
+            continue;
+        };
+        let remote = interchain
+            .chain(info.chain_id)
+            .map_err(anyhow::Error::from)?;
+        host.host_deployed = Some(remote.wasm_querier().contract_info(&host.ibc_host).is_ok());
+    }
+    Ok(())
+}
+
+/// How the home chain reaches a host, see [`ensure_host`].
+#[derive(Debug, Clone)]
+pub enum HostRoute {
+    Abstract(RemoteHost),
+    /// A pair configured for ICS-27 in the backends file.
+    Ics27 {
+        connection_id: String,
+    },
+}
+
+/// Fail with [`IcaaError::UnknownHost`] unless `host_chain` is a known host of the home chain,
+/// and with [`IcaaError::PathClosed`] unless its path is open.
+///
+/// Pairs configured for ICS-27 are reached over their connection rather than Abstract's IBC
+/// client, only the connection is checked for them. The home chain's hosts are listed once per
+/// run.
+pub fn ensure_host(home: &Daemon, host_chain: &str) -> IcaaResult<HostRoute> {
+    let target = ChainName::from_string(host_chain.to_string())?;
+    if let Backend::Ics27 { connection_id, .. } =
+        Backends::load()?.backend(&chain_id(home), host_chain)
+    {
+        if !connection_open(home, &connection_id)? {
+            return Err(IcaaError::PathClosed {
+                home: chain_name(home).to_string(),
+                host: target.to_string(),
+                path: format!("connection {connection_id}"),
+            });
+        }
+        return Ok(HostRoute::Ics27 { connection_id });
+    }
+    let hosts = cached_hosts(home)?;
+    let known: Vec<String> = hosts.iter().map(|host| host.chain.to_string()).collect();
+    let host = hosts
+        .into_iter()
+        .find(|host| host.chain == target)
+        .ok_or_else(|| IcaaError::UnknownHost {
+            home: chain_name(home).to_string(),
+            host: target.to_string(),
+            known,
+        })?;
+    if host.path_live != Some(true) {
+        let path = match (&host.polytone_channel, host.path_live) {
+            (Some(channel), None) => format!("channel {channel} in an unknown state"),
+            (Some(channel), Some(_)) => format!("channel {channel}"),
+            (None, None) => "unknown polytone channel".to_string(),
+            (None, Some(_)) => "no polytone channel".to_string(),
+        };
+        return Err(IcaaError::PathClosed {
+            home: chain_name(home).to_string(),
+            host: target.to_string(),
+            path,
+        });
+    }
+    Ok(HostRoute::Abstract(host))
+}
+
+/// [`list_remote_hosts`] of `home`, queried once per run.
+fn cached_hosts(home: &Daemon) -> IcaaResult<Vec<RemoteHost>> {
+    let mut cache = HOSTS
+        .get_or_init(Default::default)
+        .lock()
+        .expect("hosts cache lock poisoned");
+    let home_chain_id = chain_id(home);
+    if let Some(hosts) = cache.get(&home_chain_id) {
+        return Ok(hosts.clone());
+    }
+    let hosts = list_remote_hosts(home)?;
+    cache.insert(home_chain_id, hosts.clone());
+    Ok(hosts)
+}
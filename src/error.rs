@@ -0,0 +1,153 @@
+use abstract_interface::AbstractInterfaceError;
+use abstract_std::objects::AccountId;
+use abstract_std::AbstractError;
+use cosmwasm_std::{StdError, Uint128};
+use cw_orch::daemon::DaemonError;
+use cw_orch::prelude::CwOrchError;
+use thiserror::Error;
+
+use crate::timeout::TimeoutError;
+
+/// Failures of the library that callers may want to react to.
+#[derive(Debug, Error)]
+pub enum IcaaError {
+    #[error("IBC is not enabled on account {0}")]
+    IbcClientMissing(AccountId),
+
+    #[error("module {module} is not installed on account {account}")]
+    ModuleMissing { account: AccountId, module: String },
+
+    #[error("no known network for {0}")]
+    UnknownNetwork(String),
+
+    #[error("{host} is not a remote host of {home}, available hosts: {known:?}")]
+    UnknownHost {
+        home: String,
+        host: String,
+        known: Vec<String>,
+    },
+
+    #[error("the IBC path from {home} to {host} is not open ({path})")]
+    PathClosed {
+        home: String,
+        host: String,
+        /// The channel or connection that isn't open.
+        path: String,
+    },
+
+    #[error("the interchain account on {host_chain} acts for account {bound}, not {account}")]
+    IcaBound {
+        host_chain: String,
+        account: AccountId,
+        bound: AccountId,
+    },
+
+    #[error("account {account} has no remote account on {host_chain}")]
+    RemoteAccountMissing {
+        account: AccountId,
+        host_chain: String,
+    },
+
+    #[error("{owner} has no interchain account on {host_chain} over {connection_id}")]
+    InterchainAccountMissing {
+        owner: String,
+        host_chain: String,
+        connection_id: String,
+    },
+
+    #[error("account {account} already has a remote account on {host_chain}")]
+    RemoteAccountExists {
+        account: AccountId,
+        host_chain: String,
+    },
+
+    #[error("{account} is controlled by {owner}, not the wallet")]
+    NotControlled { account: AccountId, owner: String },
+
+    #[error("{account} is not a sub-account of {parent}")]
+    NotSubAccount {
+        account: AccountId,
+        parent: AccountId,
+    },
+
+    #[error("{0} has no pending ownership transfer")]
+    NoPendingTransfer(AccountId),
+
+    #[error("{account} has more than {max} parents")]
+    TooManyParents { account: AccountId, max: usize },
+
+    #[error("can't resolve {entry} through ANS on {chain}: {reason}")]
+    AnsResolution {
+        chain: String,
+        entry: String,
+        reason: String,
+    },
+
+    #[error("only native assets can be sent over ICS-20, {entry} is {info}")]
+    NonNativeAsset { entry: String, info: String },
+
+    #[error("unsupported asset type: {0}")]
+    UnsupportedAsset(String),
+
+    #[error("unknown decimals of {denom} on {chain}, add them to the decimals file")]
+    UnknownDecimals { chain: String, denom: String },
+
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("invalid account {0:?}, expected an account sequence or a namespace")]
+    InvalidAccountRef(String),
+
+    #[error("{holder} holds no {asset}")]
+    EmptyBalance { holder: String, asset: String },
+
+    #[error("{holder} holds {balance} {asset} but {required} is needed")]
+    InsufficientBalance {
+        holder: String,
+        asset: String,
+        balance: Uint128,
+        required: Uint128,
+    },
+
+    #[error(transparent)]
+    Timeout(#[from] TimeoutError),
+
+    #[error("{step} for account {account} failed on {dest_chain}: {error}")]
+    ErrorAck {
+        step: String,
+        account: AccountId,
+        dest_chain: String,
+        error: String,
+    },
+
+    #[error("{step} by ICS-27 owner {owner} over {connection_id} failed: {reason}")]
+    IcaPacket {
+        step: String,
+        owner: String,
+        connection_id: String,
+        reason: String,
+    },
+
+    #[error(transparent)]
+    Daemon(#[from] DaemonError),
+
+    #[error(transparent)]
+    CwOrch(#[from] CwOrchError),
+
+    #[error(transparent)]
+    Abstract(#[from] AbstractInterfaceError),
+
+    #[error(transparent)]
+    AbstractStd(#[from] AbstractError),
+
+    #[error(transparent)]
+    Std(#[from] StdError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub type IcaaResult<T> = Result<T, IcaaError>;
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use abstract_interface::{Abstract, AbstractAccount};
+use abstract_std::manager;
+use abstract_std::objects::gov_type::{GovAction, GovernanceDetails};
+use abstract_std::objects::AccountId;
+use cw20::Expiration;
+use cw_orch::contract::Deploy;
+use cw_orch::daemon::Daemon;
+use cw_orch::prelude::*;
+use log::warn;
+use serde::Deserialize;
+
+use crate::error::IcaaResult;
+use crate::sub_accounts::sub_account_ids;
+use crate::sweep::{self, RemoteHolding};
+use crate::{chain_name, load_account, run_log};
+
+/// `governance_type` of the external governance set for multisigs.
+pub const MULTISIG_GOVERNANCE_TYPE: &str = "multisig";
+
+/// Owner of an account's manager, with the pending transfer if any.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ownership {
+    pub owner: GovernanceDetails<String>,
+    pub pending_owner: Option<GovernanceDetails<String>>,
+    pub pending_expiry: Option<Expiration>,
+}
+
+pub fn ownership(account: &AbstractAccount<Daemon>) -> IcaaResult<Ownership> {
+    Ok(account.manager.query(&manager::QueryMsg::Ownership {})?)
+}
+
+/// Human readable governance, e.g. `monarch juno1...`.
+pub fn describe(governance: &GovernanceDetails<String>) -> String {
+    match governance {
+        GovernanceDetails::Monarch { monarch } => format!("monarch {monarch}"),
+        GovernanceDetails::SubAccount { manager, .. } => {
+            format!("sub-account of the account managed by {manager}")
+        }
+        GovernanceDetails::External {
+            governance_address,
+            governance_type,
+        } => format!("{governance_type} {governance_address}"),
+        other => format!("{other:?}"),
+    }
+}
+
+/// Who an account's governance can be handed to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NewOwner {
+    Monarch(String),
+    /// A sub-account of the local account with this sequence.
+    SubAccount(u32),
+    Multisig(String),
+}
+
+impl NewOwner {
+    /// The governance of this owner on `chain`.
+    pub fn governance(&self, chain: &Daemon) -> IcaaResult<GovernanceDetails<String>> {
+        Ok(match self {
+            NewOwner::Monarch(monarch) => GovernanceDetails::Monarch {
+                monarch: monarch.clone(),
+            },
+            NewOwner::SubAccount(parent) => {
+                let parent = load_account(chain, *parent)?;
+                GovernanceDetails::SubAccount {
+                    manager: parent.manager.addr_str()?,
+                    proxy: parent.proxy.addr_str()?,
+                }
+            }
+            NewOwner::Multisig(address) => GovernanceDetails::External {
+                governance_address: address.clone(),
+                governance_type: MULTISIG_GOVERNANCE_TYPE.to_string(),
+            },
+        })
+    }
+}
+
+/// Propose `new_owner` as the owner of `account`, valid for `expiry_blocks` blocks if given.
+///
+/// Nothing changes until the new owner accepts: the monarch or the parent's owner with
+/// [`accept`], a multisig by executing [`accept_msg`] on the manager.
+pub fn transfer(
+    account: &AbstractAccount<Daemon>,
+    new_owner: &NewOwner,
+    expiry_blocks: Option<u64>,
+) -> IcaaResult<()> {
+    let chain = account.manager.get_chain().clone();
+    let new_owner = new_owner.governance(&chain)?;
+    let expiry = match expiry_blocks {
+        Some(blocks) => Some(Expiration::AtHeight(chain.block_info()?.height + blocks)),
+        None => None,
+    };
+    warn!(
+        "Proposing {} as owner of {}",
+        describe(&new_owner),
+        account.id()?
+    );
+    let tx = account.manager.execute(
+        &manager::ExecuteMsg::UpdateOwnership(GovAction::TransferOwnership { new_owner, expiry }),
+        None,
+    )?;
+    run_log::tx(
+        "transfer ownership",
+        &chain,
+        Some(account.id()?.to_string()),
+        &tx,
+    );
+    Ok(())
+}
+
+/// The manager msg accepting a pending ownership transfer.
+pub fn accept_msg() -> manager::ExecuteMsg {
+    manager::ExecuteMsg::UpdateOwnership(GovAction::AcceptOwnership)
+}
+
+/// Accept the pending ownership transfer of `account` with the wallet.
+pub fn accept(account: &AbstractAccount<Daemon>) -> IcaaResult<()> {
+    let tx = account.manager.execute(&accept_msg(), None)?;
+    run_log::tx(
+        "accept ownership",
+        account.manager.get_chain(),
+        Some(account.id()?.to_string()),
+        &tx,
+    );
+    warn!("Accepted ownership of {}", account.id()?);
+    Ok(())
+}
+
+/// A remote account with the home account it's controlled by and its manager's owner.
+#[derive(Debug, Clone)]
+pub struct RemoteControl {
+    pub holding: RemoteHolding,
+    pub id: AccountId,
+    /// `None` when we are not connected to the chain holding the account.
+    pub owner: Option<GovernanceDetails<String>>,
+}
+
+/// The governance of an account, its sub-accounts and its remote accounts.
+#[derive(Debug, Clone)]
+pub struct GovernanceView {
+    pub id: AccountId,
+    pub chain: String,
+    pub ownership: Ownership,
+    pub sub_accounts: Vec<(AccountId, GovernanceDetails<String>)>,
+    pub remotes: Vec<RemoteControl>,
+}
+
+/// Query how `account` and everything hanging off it is governed.
+///
+/// `chains` maps the chains we're connected to by chain name, the owner of remote accounts on
+/// other chains is left unknown.
+pub fn view(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    chains: &HashMap<String, Daemon>,
+) -> IcaaResult<GovernanceView> {
+    let id = account.id()?;
+    let sub_accounts = sub_account_ids(account)?
+        .into_iter()
+        .map(|seq| {
+            let sub = load_account(home, seq)?;
+            Ok((sub.id()?, ownership(&sub)?.owner))
+        })
+        .collect::<IcaaResult<Vec<_>>>()?;
+
+    let home_chain = chain_name(home);
+    let remotes = sweep::scan(home, account, chains)?
+        .into_iter()
+        .map(|holding| {
+            let remote_id = sweep::remote_account_id(&home_chain, &id, &holding.path)?;
+            let owner = match chains.get(&holding.path.last().unwrap().to_string()) {
+                Some(remote) => {
+                    let remote_account = AbstractAccount::new(
+                        &Abstract::load_from(remote.clone())?,
+                        remote_id.clone(),
+                    );
+                    Some(ownership(&remote_account)?.owner)
+                }
+                None => None,
+            };
+            Ok(RemoteControl {
+                holding,
+                id: remote_id,
+                owner,
+            })
+        })
+        .collect::<IcaaResult<Vec<_>>>()?;
+
+    Ok(GovernanceView {
+        ownership: ownership(account)?,
+        id,
+        chain: home_chain.to_string(),
+        sub_accounts,
+        remotes,
+    })
+}
+
+impl fmt::Display for GovernanceView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Account {} on {}", self.id, self.chain)?;
+        writeln!(f, "  owner    {}", describe(&self.ownership.owner))?;
+        if let Some(pending) = &self.ownership.pending_owner {
+            writeln!(
+                f,
+                "  pending  {} (expires {})",
+                describe(pending),
+                self.ownership
+                    .pending_expiry
+                    .map_or("never".to_string(), |expiry| expiry.to_string())
+            )?;
+        }
+        for (id, owner) in &self.sub_accounts {
+            writeln!(
+                f,
+                "  sub-account {:<12} {}",
+                id.to_string(),
+                describe(owner)
+            )?;
+        }
+        // Remote accounts follow their trace: whoever controls the home account controls them.
+        for remote in &self.remotes {
+            writeln!(
+                f,
+                "  remote {:<32} {} controlled by {} on {}, owner {}",
+                remote.holding.path_string(),
+                remote.id,
+                self.id,
+                self.chain,
+                remote
+                    .owner
+                    .as_ref()
+                    .map_or("unknown (not connected)".to_string(), describe)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use abstract_std::{manager, proxy, PROXY};
+use cosmwasm_std::{from_json, CosmosMsg, Uint128, WasmMsg};
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::ChainKind;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::chain_id;
+
+/// Path of the guard config, defaults to [`DEFAULT_GUARD_FILE`].
+pub const GUARD_FILE_ENV: &str = "ICAA_GUARD";
+pub const DEFAULT_GUARD_FILE: &str = "guard.json";
+
+/// Limits applied to mainnet chains, read from the guard file:
+///
+/// ```json
+/// {
+///   "caps": { "ujuno": "10000000" },
+///   "confirm_above": { "ujuno": "1000000" },
+///   "allowed_contracts": ["osmo1..."],
+///   "allowed_code_ids": [42]
+/// }
+/// ```
+///
+/// Denoms are base denoms, or the contract address for cw20 tokens.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuardConfig {
+    /// Most that may leave the wallet or the chain per run, per denom.
+    #[serde(default)]
+    pub caps: BTreeMap<String, Uint128>,
+    /// Spends above this need confirmation, per denom.
+    #[serde(default)]
+    pub confirm_above: BTreeMap<String, Uint128>,
+    /// Contracts remote dispatches may execute or migrate.
+    #[serde(default)]
+    pub allowed_contracts: BTreeSet<String>,
+    /// Code ids remote dispatches may instantiate.
+    #[serde(default)]
+    pub allowed_code_ids: BTreeSet<u64>,
+}
+
+impl GuardConfig {
+    pub fn load() -> anyhow::Result<Self> {
+        let path = std::env::var(GUARD_FILE_ENV).unwrap_or_else(|_| DEFAULT_GUARD_FILE.to_string());
+        if !Path::new(&path).exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("invalid guard file {path}: {e}"))
+    }
+
+    /// Refuse `msg` when it runs a contract or code id that isn't allowlisted.
+    ///
+    /// Bank sends and IBC transfers run no contract code and pass, the funds they move are
+    /// accounted with [`spend`] by whoever builds them.
+    fn check_msg(&self, msg: &CosmosMsg) -> anyhow::Result<()> {
+        match msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. })
+            | CosmosMsg::Wasm(WasmMsg::Migrate { contract_addr, .. }) => {
+                if !self.allowed_contracts.contains(contract_addr) {
+                    anyhow::bail!(
+                        "remote dispatch executes {contract_addr} which isn't in the guard's \
+                         allowed_contracts"
+                    );
+                }
+            }
+            CosmosMsg::Wasm(WasmMsg::Instantiate { code_id, .. })
+            | CosmosMsg::Wasm(WasmMsg::Instantiate2 { code_id, .. }) => {
+                if !self.allowed_code_ids.contains(code_id) {
+                    anyhow::bail!(
+                        "remote dispatch instantiates code {code_id} which isn't in the guard's \
+                         allowed_code_ids"
+                    );
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Safety checks for mainnet runs, shared by the whole process.
+#[derive(Debug)]
+pub struct Guard {
+    config: GuardConfig,
+    /// Skip confirmations, caps and the allowlist still apply.
+    yes_mainnet: bool,
+    spent: Mutex<BTreeMap<String, Uint128>>,
+}
+
+static GUARD: OnceLock<Guard> = OnceLock::new();
+
+/// Set up the guard for this run, `yes_mainnet` skips confirmations.
+///
+/// Must be called before anything is sent, later calls are ignored.
+pub fn init(yes_mainnet: bool) -> anyhow::Result<()> {
+    let guard = Guard {
+        config: GuardConfig::load()?,
+        yes_mainnet,
+        spent: Mutex::new(BTreeMap::new()),
+    };
+    if GUARD.set(guard).is_err() {
+        warn!("Mainnet guard already initialized");
+    }
+    Ok(())
+}
+
+fn guard() -> anyhow::Result<&'static Guard> {
+    if GUARD.get().is_none() {
+        init(false)?;
+    }
+    Ok(GUARD.get().expect("guard initialized"))
+}
+
+fn is_mainnet(chain: &Daemon) -> bool {
+    chain.state().chain_data.kind == ChainKind::Mainnet
+}
+
+/// The user declined a mainnet confirmation.
+#[derive(Debug, Clone)]
+pub struct Aborted(pub String);
+
+impl std::fmt::Display for Aborted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "aborted: {}", self.0)
+    }
+}
+
+impl std::error::Error for Aborted {}
+
+/// Account for `amount` of `denom` leaving the wallet or `chain`, enforcing caps and confirmations.
+///
+/// Does nothing off mainnet.
+pub fn spend(chain: &Daemon, denom: &str, amount: Uint128, what: &str) -> anyhow::Result<()> {
+    if !is_mainnet(chain) {
+        return Ok(());
+    }
+    guard()?.spend(&chain_id(chain), denom, amount, what, confirm)
+}
+
+impl Guard {
+    /// [`spend`] on the mainnet `chain_id`, asking `confirm` above the confirmation threshold.
+    fn spend(
+        &self,
+        chain_id: &str,
+        denom: &str,
+        amount: Uint128,
+        what: &str,
+        confirm: impl FnOnce(&str) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<()> {
+        let mut spent = self.spent.lock().expect("guard lock poisoned");
+        let total = spent.get(denom).copied().unwrap_or_default() + amount;
+        if let Some(cap) = self.config.caps.get(denom) {
+            if total > *cap {
+                anyhow::bail!(
+                    "{what} would bring this run's spending on {chain_id} to {total}{denom}, \
+                     above the cap of {cap}{denom}"
+                );
+            }
+        }
+        if let Some(threshold) = self.config.confirm_above.get(denom) {
+            if amount > *threshold && !self.yes_mainnet {
+                let question = format!("{what}: {amount}{denom} on mainnet {chain_id}");
+                if !confirm(&question)? {
+                    return Err(Aborted(question).into());
+                }
+            }
+        }
+        spent.insert(denom.to_string(), total);
+        Ok(())
+    }
+}
+
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    print!("{question}. Continue? [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Refuse remote `msgs` executing, migrating or instantiating contracts that aren't allowlisted.
+/// Does nothing off mainnet.
+pub fn check_contracts(home: &Daemon, msgs: &[CosmosMsg]) -> anyhow::Result<()> {
+    if !is_mainnet(home) {
+        return Ok(());
+    }
+    let guard = guard()?;
+    msgs.iter().try_for_each(|msg| guard.config.check_msg(msg))
+}
+
+/// [`check_contracts`] for the proxy messages wrapped in remote manager messages.
+pub fn check_manager_msgs(
+    home: &Daemon,
+    manager_msgs: &[manager::ExecuteMsg],
+) -> anyhow::Result<()> {
+    for msg in manager_msgs {
+        if let manager::ExecuteMsg::ExecOnModule {
+            module_id,
+            exec_msg,
+        } = msg
+        {
+            if module_id != PROXY {
+                continue;
+            }
+            if let proxy::ExecuteMsg::ModuleAction { msgs } = from_json(exec_msg)? {
+                check_contracts(home, &msgs)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(config: GuardConfig, yes_mainnet: bool) -> Guard {
+        Guard {
+            config,
+            yes_mainnet,
+            spent: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn limits(denom: &str, cap: u128, confirm_above: u128) -> GuardConfig {
+        GuardConfig {
+            caps: BTreeMap::from([(denom.to_string(), Uint128::new(cap))]),
+            confirm_above: BTreeMap::from([(denom.to_string(), Uint128::new(confirm_above))]),
+            ..Default::default()
+        }
+    }
+
+    fn never_asked(question: &str) -> anyhow::Result<bool> {
+        panic!("unexpected confirmation: {question}")
+    }
+
+    #[test]
+    fn caps_apply_to_the_whole_run() {
+        let guard = guard(limits("ujuno", 100, 1_000), false);
+        guard
+            .spend("juno-1", "ujuno", Uint128::new(60), "first", never_asked)
+            .unwrap();
+        let err = guard
+            .spend("juno-1", "ujuno", Uint128::new(50), "second", never_asked)
+            .unwrap_err();
+        assert!(err.to_string().contains("above the cap of 100ujuno"));
+        // The refused spend isn't counted.
+        guard
+            .spend("juno-1", "ujuno", Uint128::new(40), "third", never_asked)
+            .unwrap();
+        guard
+            .spend("juno-1", "uatom", Uint128::new(999), "other", never_asked)
+            .unwrap();
+    }
+
+    #[test]
+    fn spends_above_the_threshold_need_confirmation() {
+        let guard = guard(limits("ujuno", 1_000, 10), false);
+        guard
+            .spend("juno-1", "ujuno", Uint128::new(10), "equal", never_asked)
+            .unwrap();
+        guard
+            .spend("juno-1", "ujuno", Uint128::new(11), "yes", |_| Ok(true))
+            .unwrap();
+        let err = guard
+            .spend("juno-1", "ujuno", Uint128::new(11), "no", |_| Ok(false))
+            .unwrap_err();
+        assert!(err.downcast_ref::<Aborted>().is_some());
+        assert_eq!(guard.spent.lock().unwrap()["ujuno"], Uint128::new(21));
+    }
+
+    #[test]
+    fn yes_mainnet_skips_confirmations_but_not_caps() {
+        let guard = guard(limits("ujuno", 100, 10), true);
+        guard
+            .spend("juno-1", "ujuno", Uint128::new(50), "large", never_asked)
+            .unwrap();
+        assert!(guard
+            .spend("juno-1", "ujuno", Uint128::new(51), "over cap", never_asked)
+            .is_err());
+    }
+
+    #[test]
+    fn allowlist_matches_contracts_and_code_ids() {
+        let config = GuardConfig {
+            allowed_contracts: BTreeSet::from(["osmo1allowed".to_string()]),
+            allowed_code_ids: BTreeSet::from([42]),
+            ..Default::default()
+        };
+        let execute = |contract: &str| {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg: Default::default(),
+                funds: vec![],
+            })
+        };
+        let instantiate = |code_id| {
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: None,
+                code_id,
+                msg: Default::default(),
+                funds: vec![],
+                label: "test".to_string(),
+            })
+        };
+        let migrate = CosmosMsg::Wasm(WasmMsg::Migrate {
+            contract_addr: "osmo1other".to_string(),
+            new_code_id: 42,
+            msg: Default::default(),
+        });
+        let send = CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+            to_address: "osmo1recipient".to_string(),
+            amount: vec![],
+        });
+
+        assert!(config.check_msg(&execute("osmo1allowed")).is_ok());
+        assert!(config.check_msg(&execute("osmo1other")).is_err());
+        assert!(config.check_msg(&migrate).is_err());
+        assert!(config.check_msg(&instantiate(42)).is_ok());
+        assert!(config.check_msg(&instantiate(7)).is_err());
+        assert!(config.check_msg(&send).is_ok());
+    }
+
+    #[test]
+    fn guard_file_fields_default_to_empty() {
+        let config: GuardConfig =
+            serde_json::from_str(r#"{ "allowed_code_ids": [1, 2] }"#).unwrap();
+        assert!(config.caps.is_empty() && config.allowed_contracts.is_empty());
+        assert_eq!(config.allowed_code_ids, BTreeSet::from([1, 2]));
+    }
+}
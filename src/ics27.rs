@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use abstract_std::objects::AccountId;
+use cosmrs::Any;
+use cosmwasm_std::CosmosMsg;
+use cw_orch::daemon::{Daemon, DaemonError};
+use cw_orch::prelude::*;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use cw_orch_interchain::types::NestedPacketsFlow;
+use log::warn;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::Channel;
+
+use crate::error::{IcaaError, IcaaResult};
+use crate::packets::{packet_results, PacketResult};
+use crate::timeout::TimeoutError;
+use crate::tx::{commit, cosmos_msg_to_any_from};
+use crate::wait::{self, WaitStrategy};
+use crate::{chain_id, run_log};
+
+/// Path of the backend selection, defaults to [`DEFAULT_BACKENDS_FILE`].
+pub const BACKENDS_FILE_ENV: &str = "ICAA_BACKENDS";
+pub const DEFAULT_BACKENDS_FILE: &str = "backends.json";
+
+const MSG_REGISTER_TYPE_URL: &str =
+    "/ibc.applications.interchain_accounts.controller.v1.MsgRegisterInterchainAccount";
+const MSG_SEND_TX_TYPE_URL: &str = "/ibc.applications.interchain_accounts.controller.v1.MsgSendTx";
+const QUERY_INTERCHAIN_ACCOUNT_PATH: &str =
+    "/ibc.applications.interchain_accounts.controller.v1.Query/InterchainAccount";
+/// `ibc.applications.interchain_accounts.v1.Type::TYPE_EXECUTE_TX`
+const TYPE_EXECUTE_TX: i32 = 1;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+/// How long a channel handshake may take before we give up on a registration, by default.
+const REGISTER_DEADLINE: Duration = Duration::from_secs(300);
+const REGISTER_POLL: Duration = Duration::from_secs(6);
+
+/// How remote accounts are reached on a host chain.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// Abstract's ibc-client and ibc-host, the default.
+    #[default]
+    Abstract,
+    /// A native ICS-27 interchain account owned by the wallet, over `connection_id`.
+    Ics27 {
+        connection_id: String,
+        /// Sequence of the local account the interchain account acts for. The wallet owns a single
+        /// interchain account per connection, so other accounts can't use the pair.
+        account: u32,
+        /// Packet timeout in seconds, 10 minutes by default.
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+    },
+}
+
+/// The backend of each chain pair, read from the backends file as home chain id to host chain
+/// name:
+///
+/// ```json
+/// { "juno-1": { "neutron": { "ics27": { "connection_id": "connection-12", "account": 7 } } } }
+/// ```
+///
+/// Pairs that aren't listed use [`Backend::Abstract`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Backends(BTreeMap<String, BTreeMap<String, Backend>>);
+
+impl Backends {
+    pub fn load() -> IcaaResult<Self> {
+        let path =
+            std::env::var(BACKENDS_FILE_ENV).unwrap_or_else(|_| DEFAULT_BACKENDS_FILE.to_string());
+        if !Path::new(&path).exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("invalid backends file {path}: {e}"))?)
+    }
+
+    /// Host chains of `home_chain_id` reached over ICS-27.
+    pub fn ics27_hosts(&self, home_chain_id: &str) -> Vec<String> {
+        self.0
+            .get(home_chain_id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, backend)| matches!(backend, Backend::Ics27 { .. }))
+            .map(|(host_chain, _)| host_chain.clone())
+            .collect()
+    }
+
+    pub fn backend(&self, home_chain_id: &str, host_chain: &str) -> Backend {
+        self.0
+            .get(home_chain_id)
+            .and_then(|hosts| hosts.get(host_chain))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The backend of the pair for `account`, failing with [`IcaaError::IcaBound`] when the pair's
+    /// interchain account acts for another account.
+    pub fn backend_for(
+        &self,
+        home_chain_id: &str,
+        host_chain: &str,
+        account: &AccountId,
+    ) -> IcaaResult<Backend> {
+        match self.backend(home_chain_id, host_chain) {
+            Backend::Ics27 { account: bound, .. } if AccountId::local(bound) != *account => {
+                Err(IcaaError::IcaBound {
+                    host_chain: host_chain.to_string(),
+                    account: account.clone(),
+                    bound: AccountId::local(bound),
+                })
+            }
+            backend => Ok(backend),
+        }
+    }
+}
+
+// The few ibc-go messages we need, encoded with the prost and tonic versions cw-orch talks to the
+// chains with, so queries run on the daemon's gRPC channel.
+
+/// `ibc.applications.interchain_accounts.controller.v1.MsgRegisterInterchainAccount`
+#[derive(Clone, PartialEq, Message)]
+struct MsgRegisterInterchainAccount {
+    #[prost(string, tag = "1")]
+    owner: String,
+    #[prost(string, tag = "2")]
+    connection_id: String,
+    /// Empty to let the host pick the default metadata.
+    #[prost(string, tag = "3")]
+    version: String,
+}
+
+/// `ibc.applications.interchain_accounts.controller.v1.MsgSendTx`
+#[derive(Clone, PartialEq, Message)]
+struct MsgSendTx {
+    #[prost(string, tag = "1")]
+    owner: String,
+    #[prost(string, tag = "2")]
+    connection_id: String,
+    #[prost(message, optional, tag = "3")]
+    packet_data: Option<InterchainAccountPacketData>,
+    /// Nanoseconds from the send time.
+    #[prost(uint64, tag = "4")]
+    relative_timeout: u64,
+}
+
+/// `ibc.applications.interchain_accounts.v1.InterchainAccountPacketData`
+#[derive(Clone, PartialEq, Message)]
+struct InterchainAccountPacketData {
+    #[prost(int32, tag = "1")]
+    r#type: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    data: Vec<u8>,
+    #[prost(string, tag = "3")]
+    memo: String,
+}
+
+/// `ibc.applications.interchain_accounts.v1.CosmosTx`
+#[derive(Clone, PartialEq, Message)]
+struct CosmosTx {
+    #[prost(message, repeated, tag = "1")]
+    messages: Vec<Any>,
+}
+
+/// `ibc.applications.interchain_accounts.controller.v1.QueryInterchainAccountRequest`
+#[derive(Clone, PartialEq, Message)]
+struct QueryInterchainAccountRequest {
+    #[prost(string, tag = "1")]
+    owner: String,
+    #[prost(string, tag = "2")]
+    connection_id: String,
+}
+
+/// `ibc.applications.interchain_accounts.controller.v1.QueryInterchainAccountResponse`
+#[derive(Clone, PartialEq, Message)]
+struct QueryInterchainAccountResponse {
+    #[prost(string, tag = "1")]
+    address: String,
+}
+
+async fn query_interchain_account(
+    channel: Channel,
+    request: QueryInterchainAccountRequest,
+) -> Result<tonic::Response<QueryInterchainAccountResponse>, tonic::Status> {
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready()
+        .await
+        .map_err(|e| tonic::Status::unknown(format!("gRPC service not ready: {e}")))?;
+    grpc.unary(
+        tonic::Request::new(request),
+        PathAndQuery::from_static(QUERY_INTERCHAIN_ACCOUNT_PATH),
+        ProstCodec::default(),
+    )
+    .await
+}
+
+/// An ICS-27 controller: the home wallet owning an interchain account on the other end of a
+/// connection.
+#[derive(Clone)]
+pub struct Controller {
+    pub home: Daemon,
+    /// The account the interchain account acts for.
+    pub account: AccountId,
+    pub host_chain: String,
+    pub connection_id: String,
+    pub timeout: Duration,
+}
+
+impl Controller {
+    /// The controller for `host_chain`, if the pair is configured for ICS-27.
+    ///
+    /// Fails with [`IcaaError::IcaBound`] when the pair's interchain account acts for another
+    /// account than `account`.
+    pub fn for_pair(
+        home: &Daemon,
+        account: &AccountId,
+        host_chain: &str,
+    ) -> IcaaResult<Option<Self>> {
+        match Backends::load()?.backend_for(&chain_id(home), host_chain, account)? {
+            Backend::Abstract => Ok(None),
+            Backend::Ics27 {
+                connection_id,
+                timeout_secs,
+                ..
+            } => Ok(Some(Self {
+                home: home.clone(),
+                account: account.clone(),
+                host_chain: host_chain.to_string(),
+                connection_id,
+                timeout: timeout_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_TIMEOUT),
+            })),
+        }
+    }
+
+    /// The wallet owning the interchain account.
+    pub fn owner(&self) -> String {
+        self.home.sender().to_string()
+    }
+
+    /// Address of the interchain account on the host, `None` until it is registered.
+    pub fn address(&self) -> IcaaResult<Option<String>> {
+        let request = QueryInterchainAccountRequest {
+            owner: self.owner(),
+            connection_id: self.connection_id.clone(),
+        };
+        let channel = self.home.channel();
+        let resp = self
+            .home
+            .rt_handle
+            .block_on(query_interchain_account(channel, request));
+        match resp {
+            Ok(resp) => Ok(Some(resp.into_inner().address)),
+            Err(status) if status.code() == tonic::Code::NotFound => Ok(None),
+            // Older ibc-go versions answer an unknown account with a generic error.
+            Err(status) if status.message().contains("failed to retrieve account") => Ok(None),
+            Err(status) => Err(DaemonError::Status(status).into()),
+        }
+    }
+
+    /// Address of the interchain account, failing with [`IcaaError::InterchainAccountMissing`]
+    /// when it isn't registered yet.
+    pub fn ensure_address(&self) -> IcaaResult<String> {
+        self.address()?
+            .ok_or_else(|| IcaaError::InterchainAccountMissing {
+                owner: self.owner(),
+                host_chain: self.host_chain.clone(),
+                connection_id: self.connection_id.clone(),
+            })
+    }
+
+    /// Register the interchain account unless it exists and wait for its channel to open, for
+    /// the deadline of `strategy` or 5 minutes.
+    ///
+    /// Fails with [`IcaaError::Timeout`] when the channel handshake doesn't complete in time.
+    pub fn register(&self, strategy: &WaitStrategy) -> IcaaResult<String> {
+        let deadline = strategy.deadline.unwrap_or(REGISTER_DEADLINE);
+        if let Some(address) = self.address()? {
+            warn!("Interchain account {} already registered", address);
+            return Ok(address);
+        }
+        warn!(
+            "Registering an interchain account on {} over {}",
+            self.host_chain, self.connection_id
+        );
+        let msg = MsgRegisterInterchainAccount {
+            owner: self.owner(),
+            connection_id: self.connection_id.clone(),
+            version: String::new(),
+        };
+        let tx = commit(
+            &self.home,
+            vec![Any {
+                type_url: MSG_REGISTER_TYPE_URL.to_string(),
+                value: msg.encode_to_vec(),
+            }],
+        )?;
+        run_log::tx("register interchain account", &self.home, None, &tx);
+
+        // The account exists once relayers completed the channel handshake, no packet to follow.
+        let start = Instant::now();
+        while start.elapsed() < deadline {
+            std::thread::sleep(REGISTER_POLL);
+            if let Some(address) = self.address()? {
+                warn!("Interchain account {} registered", address);
+                return Ok(address);
+            }
+            warn!(
+                "Waiting for the interchain account channel over {} for {}s",
+                self.connection_id,
+                start.elapsed().as_secs()
+            );
+        }
+        Err(TimeoutError {
+            step: format!(
+                "opening the interchain account channel over {} within {}s",
+                self.connection_id,
+                deadline.as_secs()
+            ),
+            account: self.account.clone(),
+            sent: 0,
+            timeouts: vec![],
+        }
+        .into())
+    }
+
+    /// Execute `msgs` from the interchain account and wait for the packet's ack.
+    pub fn execute(
+        &self,
+        interchain: &DaemonInterchainEnv,
+        step: &str,
+        msgs: Vec<CosmosMsg>,
+        strategy: &WaitStrategy,
+    ) -> IcaaResult<NestedPacketsFlow<Daemon>> {
+        let address = self.ensure_address()?;
+        let messages = msgs
+            .iter()
+            .map(|msg| cosmos_msg_to_any_from(&address, msg))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let msg = MsgSendTx {
+            owner: self.owner(),
+            connection_id: self.connection_id.clone(),
+            packet_data: Some(InterchainAccountPacketData {
+                r#type: TYPE_EXECUTE_TX,
+                data: CosmosTx { messages }.encode_to_vec(),
+                memo: String::new(),
+            }),
+            relative_timeout: self.timeout.as_nanos() as u64,
+        };
+        let tx = commit(
+            &self.home,
+            vec![Any {
+                type_url: MSG_SEND_TX_TYPE_URL.to_string(),
+                value: msg.encode_to_vec(),
+            }],
+        )?;
+        run_log::tx(step, &self.home, None, &tx);
+        let flow = wait::wait(interchain, &self.home, step, tx, strategy)?.done()?;
+        for packet in packet_results(&flow) {
+            let reason = match packet {
+                PacketResult::Success { .. } => continue,
+                PacketResult::ErrorAck { error, .. } => error,
+                // Timeouts close ordered ICS-27 channels, the account needs a new channel.
+                PacketResult::Timeout => {
+                    "packet timed out, the channel is closed until the account is re-registered"
+                        .to_string()
+                }
+            };
+            return Err(IcaaError::IcaPacket {
+                step: step.to_string(),
+                owner: self.owner(),
+                connection_id: self.connection_id.clone(),
+                reason,
+            });
+        }
+        Ok(flow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backends() -> Backends {
+        serde_json::from_str(
+            r#"{
+                "juno-1": {
+                    "neutron": { "ics27": { "connection_id": "connection-12", "account": 7 } },
+                    "osmosis": "abstract"
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_the_backends_file() {
+        assert_eq!(
+            backends().backend("juno-1", "neutron"),
+            Backend::Ics27 {
+                connection_id: "connection-12".to_string(),
+                account: 7,
+                timeout_secs: None,
+            }
+        );
+        assert_eq!(backends().backend("juno-1", "osmosis"), Backend::Abstract);
+    }
+
+    #[test]
+    fn unlisted_pairs_use_abstract() {
+        assert_eq!(backends().backend("juno-1", "archway"), Backend::Abstract);
+        assert_eq!(
+            backends().backend("osmosis-1", "neutron"),
+            Backend::Abstract
+        );
+        assert_eq!(
+            Backends::default().backend("juno-1", "neutron"),
+            Backend::Abstract
+        );
+    }
+
+    #[test]
+    fn lists_ics27_hosts_only() {
+        assert_eq!(
+            backends().ics27_hosts("juno-1"),
+            vec!["neutron".to_string()]
+        );
+        assert!(backends().ics27_hosts("osmosis-1").is_empty());
+    }
+
+    #[test]
+    fn interchain_account_acts_for_its_account_only() {
+        let backends = backends();
+        assert!(matches!(
+            backends.backend_for("juno-1", "neutron", &AccountId::local(7)),
+            Ok(Backend::Ics27 { .. })
+        ));
+        assert!(matches!(
+            backends.backend_for("juno-1", "neutron", &AccountId::local(8)),
+            Err(IcaaError::IcaBound { bound, .. }) if bound == AccountId::local(7)
+        ));
+        assert_eq!(
+            backends
+                .backend_for("juno-1", "osmosis", &AccountId::local(8))
+                .unwrap(),
+            Backend::Abstract
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use abstract_interface::{Abstract, AbstractAccount};
+use abstract_std::manager::{self, InfoResponse};
+use abstract_std::objects::namespace::Namespace;
+use abstract_std::objects::AccountId;
+use abstract_std::version_control::{NamespaceResponse, QueryMsgFns as VcQueryFns};
+use cosmwasm_std::Coin;
+use cw_orch::contract::Deploy;
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::BankQuerier;
+use cw_orch::prelude::*;
+
+use crate::error::IcaaError;
+use crate::modules::{installed_modules, InstalledModule};
+use crate::sweep::{self, RemoteHolding};
+use crate::{chain_id, chain_name, IBC_CLIENT_ID};
+
+/// An account given by its sequence on the home chain or by the namespace it claimed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountRef {
+    Seq(u32),
+    Namespace(String),
+}
+
+impl FromStr for AccountRef {
+    type Err = IcaaError;
+
+    /// A number is a sequence, anything else a namespace of lowercase letters, digits and dashes
+    /// that doesn't start with a dash.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(seq) = s.parse() {
+            return Ok(AccountRef::Seq(seq));
+        }
+        let namespace = !s.is_empty()
+            && !s.starts_with('-')
+            && s.chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !namespace {
+            return Err(IcaaError::InvalidAccountRef(s.to_string()));
+        }
+        Ok(AccountRef::Namespace(s.to_string()))
+    }
+}
+
+/// Load the account `account` refers to on `chain`.
+pub fn load(chain: &Daemon, account: &AccountRef) -> anyhow::Result<AbstractAccount<Daemon>> {
+    let abstr = Abstract::load_from(chain.clone())?;
+    let id = match account {
+        AccountRef::Seq(seq) => AccountId::local(*seq),
+        AccountRef::Namespace(namespace) => {
+            match abstr
+                .version_control
+                .namespace(Namespace::new(namespace)?)?
+            {
+                NamespaceResponse::Claimed(info) => info.account_id,
+                NamespaceResponse::Unclaimed {} => anyhow::bail!(
+                    "namespace {} is not claimed on {}",
+                    namespace,
+                    chain_name(chain)
+                ),
+            }
+        }
+    };
+    Ok(AbstractAccount::new(&abstr, id))
+}
+
+/// A remote account of the inspected account with what we could query of it.
+#[derive(Debug, Clone)]
+pub struct RemoteView {
+    pub holding: RemoteHolding,
+    /// `None` when we are not connected to the chain holding the account.
+    pub modules: Option<Vec<InstalledModule>>,
+}
+
+/// The state of an account on its home chain and on every remote chain it reaches.
+#[derive(Debug, Clone)]
+pub struct AccountView {
+    pub id: AccountId,
+    pub chain_id: String,
+    pub name: String,
+    pub manager: String,
+    pub proxy: String,
+    pub ibc_enabled: bool,
+    pub modules: Vec<InstalledModule>,
+    pub balances: Vec<Coin>,
+    pub remotes: Vec<RemoteView>,
+}
+
+/// Query everything about `account` without sending a transaction.
+///
+/// `chains` maps the chains we're connected to by chain name, remote accounts on other chains
+/// are listed without balances or modules.
+pub fn inspect(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    chains: &HashMap<String, Daemon>,
+) -> anyhow::Result<AccountView> {
+    let id = account.id()?;
+    let info: InfoResponse = account.manager.query(&manager::QueryMsg::Info {})?;
+    let proxy = account.proxy.address()?;
+    let balances = home.bank_querier().balance(proxy.clone(), None)?;
+    let modules = installed_modules(account)?;
+    let ibc_enabled = modules.iter().any(|module| module.id == IBC_CLIENT_ID);
+
+    let home_chain = chain_name(home);
+    let remotes = sweep::scan(home, account, chains)?
+        .into_iter()
+        .map(|holding| {
+            let Some(remote) = chains.get(&holding.path.last().unwrap().to_string()) else {
+                return Ok(RemoteView {
+                    holding,
+                    modules: None,
+                });
+            };
+            let remote_account = AbstractAccount::new(
+                &Abstract::load_from(remote.clone())?,
+                sweep::remote_account_id(&home_chain, &id, &holding.path)?,
+            );
+            Ok(RemoteView {
+                modules: Some(installed_modules(&remote_account)?),
+                holding,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(AccountView {
+        id,
+        chain_id: chain_id(home),
+        name: info.info.name,
+        manager: account.manager.address()?.to_string(),
+        proxy: proxy.to_string(),
+        ibc_enabled,
+        modules,
+        balances,
+        remotes,
+    })
+}
+
+fn write_modules(f: &mut fmt::Formatter<'_>, modules: &[InstalledModule]) -> fmt::Result {
+    for module in modules {
+        writeln!(
+            f,
+            "    {:<32} {:<10} {}",
+            module.id, module.version, module.address
+        )?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for AccountView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Account {} ({}) on {}",
+            self.id, self.name, self.chain_id
+        )?;
+        writeln!(f, "  manager  {}", self.manager)?;
+        writeln!(f, "  proxy    {}", self.proxy)?;
+        writeln!(
+            f,
+            "  IBC      {}",
+            if self.ibc_enabled {
+                "enabled"
+            } else {
+                "not enabled"
+            }
+        )?;
+        writeln!(f, "  balances {:?}", self.balances)?;
+        writeln!(f, "  modules:")?;
+        write_modules(f, &self.modules)?;
+        if self.remotes.is_empty() {
+            return writeln!(f, "  no remote accounts");
+        }
+        writeln!(f, "  remote accounts:")?;
+        for remote in &self.remotes {
+            let holding = &remote.holding;
+            writeln!(
+                f,
+                "  {:<32} proxy {}",
+                holding.path_string(),
+                holding.proxy.as_deref().unwrap_or("unknown")
+            )?;
+            match (&holding.balances, &remote.modules) {
+                (Some(balances), Some(modules)) => {
+                    writeln!(f, "    balances {:?}", balances)?;
+                    write_modules(f, modules)?;
+                }
+                _ => writeln!(f, "    not connected")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_refs_are_sequences_or_namespaces() {
+        assert_eq!("7".parse::<AccountRef>().unwrap(), AccountRef::Seq(7));
+        assert_eq!("0".parse::<AccountRef>().unwrap(), AccountRef::Seq(0));
+        assert_eq!(
+            "my-dao".parse::<AccountRef>().unwrap(),
+            AccountRef::Namespace("my-dao".to_string())
+        );
+        assert_eq!(
+            "dao42".parse::<AccountRef>().unwrap(),
+            AccountRef::Namespace("dao42".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_account_refs_are_refused() {
+        for input in ["", "My-Dao", "my dao", "-1", "juno>juno", "4294967296x"] {
+            assert!(
+                matches!(
+                    input.parse::<AccountRef>(),
+                    Err(IcaaError::InvalidAccountRef(refused)) if refused == input
+                ),
+                "{input:?} was accepted"
+            );
+        }
+    }
+}
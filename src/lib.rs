@@ -1,9 +1,43 @@
+pub mod amount;
+pub mod batch;
+pub mod bench;
+pub mod costs;
+pub mod discovery;
+pub mod error;
+pub mod governance;
+pub mod guard;
+pub mod ics27;
+pub mod inspect;
+pub mod modules;
+pub mod nft;
+pub mod packets;
+pub mod provision;
+pub mod recovery;
+pub mod remote;
+pub mod run_log;
+pub mod runner;
+pub mod signer;
+pub mod sub_accounts;
+pub mod sweep;
+pub mod timeout;
+pub mod token;
+pub mod trace;
+pub mod transfer;
+pub mod tx;
+pub mod wait;
+
 use abstract_core::ibc_client::QueryMsgFns;
 use abstract_core::objects::chain_name::ChainName;
-use abstract_interface::{AbstractAccount, IbcClient};
+use abstract_core::objects::AccountId;
+use abstract_interface::{Abstract, AbstractAccount, IbcClient};
 use abstract_std as abstract_core;
+use cw_orch::contract::Deploy;
+use cw_orch::daemon::networks::parse_network;
 use cw_orch::daemon::Daemon;
-use cw_orch::prelude::ContractInstance;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use tokio::runtime::Runtime;
+
+use error::{IcaaError, IcaaResult};
 
 use cw_orch::daemon::networks::juno::JUNO_NETWORK;
 use cw_orch::environment::{ChainInfo, ChainKind};
@@ -22,19 +56,58 @@ pub const JUNO_1: ChainInfo = ChainInfo {
 
 pub const IBC_CLIENT_ID: &str = "abstract:ibc-client";
 
-// @feedback: it would be really nice to be able to query a module directly from the account
+/// Resolve a chain id to its [`ChainInfo`], preferring our own overrides (like [`JUNO_1`]).
+pub fn chain_info(chain_id: &str) -> IcaaResult<ChainInfo> {
+    match chain_id {
+        id if id == JUNO_1.chain_id => Ok(JUNO_1),
+        id => parse_network(id).map_err(|e| anyhow::anyhow!(e).into()),
+    }
+}
+
+/// Connect to `home` and every chain in `remotes` in a single interchain environment, see
+/// [`signer::connect_chains`] for the keys used.
+pub fn connect(rt: &Runtime, home: &str, remotes: &[&str]) -> anyhow::Result<DaemonInterchainEnv> {
+    let mut chains = vec![chain_info(home)?];
+    for remote in remotes {
+        chains.push(chain_info(remote)?);
+    }
+    signer::connect_chains(rt, chains)
+}
+
+pub fn chain_id(chain: &Daemon) -> String {
+    chain.state().chain_data.chain_id.to_string()
+}
+
+/// The Abstract chain name (e.g. `juno`) of a daemon.
+pub fn chain_name(chain: &Daemon) -> ChainName {
+    ChainName::from_chain_id(&chain_id(chain))
+}
+
+/// Load a local account on `chain` by its sequence.
+pub fn load_account(chain: &Daemon, seq: u32) -> IcaaResult<AbstractAccount<Daemon>> {
+    let abstr = Abstract::load_from(chain.clone())?;
+    Ok(AbstractAccount::new(&abstr, AccountId::local(seq)))
+}
+
+/// Remote proxies of `account` by chain, failing with [`IcaaError::IbcClientMissing`] when IBC
+/// isn't enabled on it.
 pub fn list_remote_proxies(
     chain: &Daemon,
     account: &AbstractAccount<Daemon>,
-) -> anyhow::Result<Vec<(ChainName, Option<String>)>> {
-    let ibc_client = IbcClient::new(IBC_CLIENT_ID, chain.clone());
-    ibc_client.set_address(&account.manager.module_info(IBC_CLIENT_ID)?.unwrap().address);
+) -> IcaaResult<Vec<(ChainName, Option<String>)>> {
+    let ibc_client = match modules::module(account, IBC_CLIENT_ID, IbcClient::new) {
+        Err(IcaaError::ModuleMissing { account, .. }) => {
+            return Err(IcaaError::IbcClientMissing(account))
+        }
+        ibc_client => ibc_client?,
+    };
     let remote_proxies = ibc_client
         .list_remote_proxies_by_account_id(account.id()?)?
         .proxies;
     println!(
-        " Found {:?} remote proxies on: {:?}",
+        " Found {:?} remote proxies on {} for: {:?}",
         remote_proxies,
+        chain_id(chain),
         account.id()?,
     );
     Ok(remote_proxies)
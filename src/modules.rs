@@ -0,0 +1,129 @@
+use std::fmt::Debug;
+
+use abstract_interface::{Abstract, AbstractAccount};
+use abstract_std::manager::{self, ModuleInfosResponse};
+use abstract_std::objects::AccountId;
+use cosmwasm_std::Addr;
+use cw_orch::contract::Deploy;
+use cw_orch::daemon::Daemon;
+use cw_orch::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::chain_name;
+use crate::error::{IcaaError, IcaaResult};
+use crate::sweep::remote_account_id;
+
+const PAGE_LIMIT: u8 = 10;
+
+/// A module installed on an account's manager.
+#[derive(Debug, Clone)]
+pub struct InstalledModule {
+    pub id: String,
+    pub version: String,
+    pub address: String,
+}
+
+/// Every module installed on the manager of `account`.
+pub fn installed_modules(account: &AbstractAccount<Daemon>) -> IcaaResult<Vec<InstalledModule>> {
+    let mut modules: Vec<InstalledModule> = vec![];
+    loop {
+        let page: ModuleInfosResponse = account.manager.query(&manager::QueryMsg::ModuleInfos {
+            start_after: modules.last().map(|module| module.id.clone()),
+            limit: Some(PAGE_LIMIT),
+        })?;
+        let done = page.module_infos.len() < PAGE_LIMIT as usize;
+        modules.extend(page.module_infos.into_iter().map(|info| InstalledModule {
+            id: info.id,
+            version: info.version.version,
+            address: info.address.to_string(),
+        }));
+        if done {
+            return Ok(modules);
+        }
+    }
+}
+
+/// Address of `module_id` on `account`, failing with [`IcaaError::ModuleMissing`] when it isn't
+/// installed.
+pub fn module_address(account: &AbstractAccount<Daemon>, module_id: &str) -> IcaaResult<Addr> {
+    match account.manager.module_info(module_id)? {
+        Some(module) => Ok(module.address),
+        None => Err(IcaaError::ModuleMissing {
+            account: account.id()?,
+            module: module_id.to_string(),
+        }),
+    }
+}
+
+/// The `module_id` module of `account`, as a typed interface ready for queries and executes.
+///
+/// `interface` builds the interface from its id and the account's chain, the cw-orch `new` of
+/// the interface fits, e.g. `module(&account, IBC_CLIENT_ID, IbcClient::new)`.
+pub fn module<T: ContractInstance<Daemon>>(
+    account: &AbstractAccount<Daemon>,
+    module_id: &str,
+    interface: impl FnOnce(&str, Daemon) -> T,
+) -> IcaaResult<T> {
+    let address = module_address(account, module_id)?;
+    let module = interface(module_id, account.manager.get_chain().clone());
+    module.set_address(&address);
+    Ok(module)
+}
+
+/// A module of a remote account, only fit for queries.
+///
+/// The interface would execute as the local wallet rather than the remote account, so executes
+/// go through [`crate::remote::execute_remote`] with a message to [`RemoteModule::address`].
+#[derive(Clone)]
+pub struct RemoteModule<T> {
+    module: T,
+}
+
+impl<T: CwOrchQuery<Daemon>> RemoteModule<T> {
+    pub fn query<G: Serialize + DeserializeOwned + Debug>(
+        &self,
+        query_msg: &T::QueryMsg,
+    ) -> IcaaResult<G> {
+        Ok(self.module.query(query_msg)?)
+    }
+
+    pub fn address(&self) -> IcaaResult<Addr> {
+        Ok(self.module.address()?)
+    }
+}
+
+/// The `module_id` module of the remote account of `account_id`, an account living on `home`,
+/// at the end of `path`.
+///
+/// `path` lists every hop from `home`, e.g. `[juno, osmosis]` for the account that `account_id`
+/// controls on osmosis through its remote account on juno.
+pub fn remote_module<T: ContractInstance<Daemon>>(
+    home: &Daemon,
+    account_id: &AccountId,
+    path: &[Daemon],
+    module_id: &str,
+    interface: impl FnOnce(&str, Daemon) -> T,
+) -> IcaaResult<RemoteModule<T>> {
+    let account = remote_account(home, account_id, path)?;
+    Ok(RemoteModule {
+        module: module(&account, module_id, interface)?,
+    })
+}
+
+/// The remote account of `account_id` at the end of `path`, see [`remote_module`].
+fn remote_account(
+    home: &Daemon,
+    account_id: &AccountId,
+    path: &[Daemon],
+) -> IcaaResult<AbstractAccount<Daemon>> {
+    let remote = path
+        .last()
+        .ok_or_else(|| anyhow::anyhow!("empty remote path"))?;
+    let names: Vec<_> = path.iter().map(chain_name).collect();
+    let id = remote_account_id(&chain_name(home), account_id, &names)?;
+    Ok(AbstractAccount::new(
+        &Abstract::load_from(remote.clone())?,
+        id,
+    ))
+}
@@ -0,0 +1,202 @@
+use abstract_interface::AbstractAccount;
+use cosmwasm_std::{wasm_execute, Binary, CosmosMsg, Empty};
+use cw721_base::{ExecuteMsg as NftExecuteMsg, QueryMsg as NftQueryMsg};
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::WasmQuerier;
+use cw_orch::prelude::*;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use cw_orch_interchain::types::NestedPacketsFlow;
+use log::warn;
+use serde::Deserialize;
+
+use crate::chain_name;
+use crate::error::IcaaResult;
+use crate::remote::{execute_remote, remote_proxy};
+use crate::wait::WaitStrategy;
+
+const PAGE_LIMIT: u32 = 30;
+
+/// Free-form token metadata, stored as the cw721 extension.
+pub type Metadata = Option<serde_json::Value>;
+
+/// A cw721 operation executed by a remote account on its collection.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NftAction {
+    /// Mint `token_id`, to the remote account itself unless `owner` is set. The remote account
+    /// must be the collection's minter.
+    Mint {
+        token_id: String,
+        owner: Option<String>,
+        token_uri: Option<String>,
+        metadata: Metadata,
+    },
+    Transfer {
+        token_id: String,
+        recipient: String,
+    },
+    /// Send `token_id` to `contract`, which receives `msg` in a `ReceiveNft` hook.
+    Send {
+        token_id: String,
+        contract: String,
+        msg: Binary,
+    },
+}
+
+impl NftAction {
+    fn token_id(&self) -> &str {
+        match self {
+            NftAction::Mint { token_id, .. }
+            | NftAction::Transfer { token_id, .. }
+            | NftAction::Send { token_id, .. } => token_id,
+        }
+    }
+
+    fn to_msg(&self, collection: &str, remote_proxy: &str) -> IcaaResult<CosmosMsg> {
+        let msg = match self.clone() {
+            NftAction::Mint {
+                token_id,
+                owner,
+                token_uri,
+                metadata,
+            } => NftExecuteMsg::<Metadata, Empty>::Mint {
+                token_id,
+                owner: owner.unwrap_or_else(|| remote_proxy.to_string()),
+                token_uri,
+                extension: metadata,
+            },
+            NftAction::Transfer {
+                token_id,
+                recipient,
+            } => NftExecuteMsg::TransferNft {
+                recipient,
+                token_id,
+            },
+            NftAction::Send {
+                token_id,
+                contract,
+                msg,
+            } => NftExecuteMsg::SendNft {
+                contract,
+                token_id,
+                msg,
+            },
+        };
+        Ok(wasm_execute(collection, &msg, vec![])?.into())
+    }
+}
+
+/// Execute `actions` on the cw721 `collection` on `host_chain` from `account`'s remote account,
+/// all in one IBC packet.
+pub fn execute(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    host_chain: &str,
+    collection: &str,
+    actions: &[NftAction],
+    strategy: &WaitStrategy,
+) -> IcaaResult<NestedPacketsFlow<Daemon>> {
+    let proxy = remote_proxy(home, account, host_chain)?;
+    let msgs = actions
+        .iter()
+        .map(|action| action.to_msg(collection, &proxy))
+        .collect::<IcaaResult<Vec<_>>>()?;
+    let tokens: Vec<&str> = actions.iter().map(NftAction::token_id).collect();
+    warn!(
+        "Executing {} NFT actions on {} ({:?}) from {}",
+        actions.len(),
+        collection,
+        tokens,
+        proxy
+    );
+    execute_remote(
+        interchain,
+        home,
+        account,
+        host_chain,
+        &format!("nft actions on {host_chain}"),
+        msgs,
+        strategy,
+    )
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OwnerOfResponse {
+    pub owner: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NftInfoResponse {
+    pub token_uri: Option<String>,
+    pub extension: Metadata,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TokensResponse {
+    tokens: Vec<String>,
+}
+
+/// Current owner of `token_id` in `collection`.
+pub fn owner_of(remote: &Daemon, collection: &str, token_id: &str) -> IcaaResult<String> {
+    let resp: OwnerOfResponse = remote.wasm_querier().smart_query(
+        collection,
+        &NftQueryMsg::<Empty>::OwnerOf {
+            token_id: token_id.to_string(),
+            include_expired: None,
+        },
+    )?;
+    Ok(resp.owner)
+}
+
+pub fn nft_info(remote: &Daemon, collection: &str, token_id: &str) -> IcaaResult<NftInfoResponse> {
+    Ok(remote.wasm_querier().smart_query(
+        collection,
+        &NftQueryMsg::<Empty>::NftInfo {
+            token_id: token_id.to_string(),
+        },
+    )?)
+}
+
+/// Every token of `collection` owned by `owner`.
+pub fn tokens_of(remote: &Daemon, collection: &str, owner: &str) -> IcaaResult<Vec<String>> {
+    let mut tokens: Vec<String> = vec![];
+    loop {
+        let page: TokensResponse = remote.wasm_querier().smart_query(
+            collection,
+            &NftQueryMsg::<Empty>::Tokens {
+                owner: owner.to_string(),
+                start_after: tokens.last().cloned(),
+                limit: Some(PAGE_LIMIT),
+            },
+        )?;
+        let done = page.tokens.len() < PAGE_LIMIT as usize;
+        tokens.extend(page.tokens);
+        if done {
+            return Ok(tokens);
+        }
+    }
+}
+
+/// Tokens held by a remote proxy, by collection.
+#[derive(Debug, Clone)]
+pub struct NftHoldings {
+    pub proxy: String,
+    pub collections: Vec<(String, Vec<String>)>,
+}
+
+/// The tokens the remote proxy of `account` on `remote` holds in each of `collections`.
+///
+/// cw721 has no index of the collections an address holds tokens of, so they must be given.
+pub fn holdings(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    remote: &Daemon,
+    collections: &[String],
+) -> IcaaResult<NftHoldings> {
+    let proxy = remote_proxy(home, account, &chain_name(remote).to_string())?;
+    let collections = collections
+        .iter()
+        .map(|collection| Ok((collection.clone(), tokens_of(remote, collection, &proxy)?)))
+        .collect::<IcaaResult<Vec<_>>>()?;
+    Ok(NftHoldings { proxy, collections })
+}
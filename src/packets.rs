@@ -0,0 +1,34 @@
+use cw_orch::daemon::Daemon;
+use cw_orch_interchain::types::{IbcPacketAckDecode, IbcPacketOutcome, NestedPacketsFlow};
+
+/// What happened to a single IBC packet, including packets sent by the receiving transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PacketResult {
+    Success { dest_chain: String },
+    ErrorAck { dest_chain: String, error: String },
+    Timeout,
+}
+
+/// Flatten every packet of a (possibly multi-hop) flow into its result.
+pub fn packet_results(flow: &NestedPacketsFlow<Daemon>) -> Vec<PacketResult> {
+    let mut results = vec![];
+    for packet in &flow.packets {
+        match &packet.outcome {
+            IbcPacketOutcome::Timeout { .. } => results.push(PacketResult::Timeout),
+            IbcPacketOutcome::Success {
+                receive_tx, ack, ..
+            } => {
+                let dest_chain = receive_tx.tx_id.chain_id.clone();
+                results.push(match ack {
+                    IbcPacketAckDecode::Error(error) => PacketResult::ErrorAck {
+                        dest_chain,
+                        error: error.clone(),
+                    },
+                    _ => PacketResult::Success { dest_chain },
+                });
+                results.extend(packet_results(receive_tx));
+            }
+        }
+    }
+    results
+}
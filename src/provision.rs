@@ -0,0 +1,196 @@
+use abstract_interface::AbstractAccount;
+use abstract_std::objects::chain_name::ChainName;
+use cw_orch::daemon::Daemon;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use log::warn;
+
+use crate::batch::Batch;
+use crate::discovery::ensure_host;
+use crate::error::{IcaaError, IcaaResult};
+use crate::packets::{packet_results, PacketResult};
+use crate::run_log;
+use crate::wait::{self, WaitStrategy};
+use crate::{ics27, list_remote_proxies};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProvisionStatus {
+    AlreadyRegistered,
+    Registered,
+    /// The registration was sent but no remote account exists afterwards.
+    Failed(String),
+    /// The host was not usable, nothing was sent.
+    Refused(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ProvisionResult {
+    pub chain: ChainName,
+    pub status: ProvisionStatus,
+    pub remote_proxy: Option<String>,
+}
+
+/// Register remote accounts for `account` on every chain in `host_chains` at once.
+///
+/// With `batch` all `Register` messages go out in a single home transaction, otherwise one
+/// transaction per chain is submitted back-to-back. Either way all packets are awaited together,
+/// up to the deadline of `strategy`; the results need the outcome so the waits are never detached.
+///
+/// Pairs configured for ICS-27 register the wallet's interchain account instead, one after the
+/// other, and don't need IBC enabled on the account.
+pub fn provision(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    host_chains: &[&str],
+    batch: bool,
+    strategy: &WaitStrategy,
+) -> IcaaResult<Vec<ProvisionResult>> {
+    let strategy = strategy.clone().detach(false);
+    let account_id = account.id()?;
+    let existing = match list_remote_proxies(home, account) {
+        Ok(existing) => Some(existing),
+        Err(IcaaError::IbcClientMissing(_)) => None,
+        Err(err) => return Err(err),
+    };
+    let mut results = vec![];
+    let mut pending = vec![];
+    let mut interchain_accounts = vec![];
+    for host_chain in host_chains {
+        let chain = ChainName::from_string(host_chain.to_string())?;
+        match ics27::Controller::for_pair(home, &account_id, host_chain) {
+            Err(err) => results.push(refused(chain, err)),
+            Ok(Some(controller)) => match ensure_host(home, host_chain) {
+                Ok(_) => interchain_accounts.push((chain, controller)),
+                Err(err) => results.push(refused(chain, err)),
+            },
+            Ok(None) => {
+                let Some(existing) = &existing else {
+                    results.push(refused(
+                        chain,
+                        IcaaError::IbcClientMissing(account_id.clone()),
+                    ));
+                    continue;
+                };
+                if let Some((_, proxy)) = existing.iter().find(|(c, _)| c == &chain) {
+                    results.push(ProvisionResult {
+                        chain,
+                        status: ProvisionStatus::AlreadyRegistered,
+                        remote_proxy: proxy.clone(),
+                    });
+                } else if let Err(err) = ensure_host(home, host_chain) {
+                    results.push(refused(chain, err));
+                } else {
+                    pending.push(chain);
+                }
+            }
+        }
+    }
+
+    for (chain, controller) in interchain_accounts {
+        let (status, remote_proxy) = match controller.address()? {
+            Some(address) => (ProvisionStatus::AlreadyRegistered, Some(address)),
+            None => match controller.register(&strategy) {
+                Ok(address) => (ProvisionStatus::Registered, Some(address)),
+                Err(err) => (ProvisionStatus::Failed(err.to_string()), None),
+            },
+        };
+        results.push(ProvisionResult {
+            chain,
+            status,
+            remote_proxy,
+        });
+    }
+    if pending.is_empty() {
+        return Ok(results);
+    }
+
+    warn!("Registering remote accounts on {:?}", pending);
+    let flows = if batch {
+        pending
+            .iter()
+            .fold(Batch::new(), |batch, chain| {
+                batch.register_remote(chain.to_string())
+            })
+            .submit(interchain, home, account, &strategy)?
+            .into_iter()
+            .filter_map(|stage| stage.flow)
+            .collect()
+    } else {
+        // Waits start as soon as each tx lands, so a failed submission still leaves the
+        // remote accounts registered before it awaited (or recorded as pending) and logged.
+        std::thread::scope(|s| {
+            let mut handles = vec![];
+            let mut submit_error = None;
+            for chain in &pending {
+                let tx = match account.register_remote_account(&chain.to_string()) {
+                    Ok(tx) => tx,
+                    Err(err) => {
+                        submit_error = Some(err);
+                        break;
+                    }
+                };
+                let step = format!("register remote account on {chain}");
+                run_log::tx(&step, home, Some(account_id.to_string()), &tx);
+                let home = home.clone();
+                let strategy = &strategy;
+                handles.push(s.spawn(move || -> IcaaResult<_> {
+                    Ok(wait::wait(interchain, &home, &step, tx, strategy)?.done()?)
+                }));
+            }
+            let flows = handles
+                .into_iter()
+                .map(|handle| handle.join().expect("IBC wait thread panicked"))
+                .collect::<IcaaResult<Vec<_>>>();
+            match submit_error {
+                Some(err) => Err(err.into()),
+                None => flows,
+            }
+        })?
+    };
+
+    let packets: Vec<PacketResult> = flows.iter().flat_map(packet_results).collect();
+    let timeouts = packets
+        .iter()
+        .filter(|packet| matches!(packet, PacketResult::Timeout))
+        .count();
+    let after = list_remote_proxies(home, account)?;
+    for chain in pending {
+        let result = match after.iter().find(|(c, _)| c == &chain) {
+            Some((_, proxy)) => ProvisionResult {
+                chain,
+                status: ProvisionStatus::Registered,
+                remote_proxy: proxy.clone(),
+            },
+            None => {
+                let error = packets
+                    .iter()
+                    .find_map(|packet| match packet {
+                        PacketResult::ErrorAck { dest_chain, error }
+                            if ChainName::from_chain_id(dest_chain) == chain =>
+                        {
+                            Some(error.clone())
+                        }
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| {
+                        format!("no remote account after waiting ({timeouts} packets timed out)")
+                    });
+                ProvisionResult {
+                    chain,
+                    status: ProvisionStatus::Failed(error),
+                    remote_proxy: None,
+                }
+            }
+        };
+        results.push(result);
+    }
+    Ok(results)
+}
+
+fn refused(chain: ChainName, err: IcaaError) -> ProvisionResult {
+    ProvisionResult {
+        chain,
+        status: ProvisionStatus::Refused(err.to_string()),
+        remote_proxy: None,
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use abstract_interface::{Abstract, AbstractAccount};
+use abstract_std::manager;
+use abstract_std::objects::gov_type::GovernanceDetails;
+use abstract_std::objects::AccountId;
+use cw_orch::contract::Deploy;
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::WasmQuerier;
+use cw_orch::prelude::*;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use log::warn;
+use serde::Deserialize;
+
+use crate::error::{IcaaError, IcaaResult};
+use crate::governance::{self, describe, NewOwner};
+use crate::ics27::{Backends, Controller};
+use crate::remote::execute_remote;
+use crate::sweep::{self, RemoteHolding};
+use crate::wait::WaitStrategy;
+use crate::{chain_id, chain_name, load_account};
+
+/// Sub-accounts nest at most this deep, stops walking up a misconfigured governance.
+const MAX_PARENTS: usize = 8;
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManagerConfig {
+    account_id: AccountId,
+}
+
+/// Whether a remote account still obeys its home account.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteStatus {
+    /// Owned by the ibc-host of its chain, so packets from the home account reach it.
+    Reachable,
+    /// The home chain's IBC client has no proxy for it.
+    NotRegistered,
+    /// Its manager answers to someone else than the ibc-host.
+    ForeignOwner(GovernanceDetails<String>),
+    /// We are not connected to the chain holding it.
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteAudit {
+    pub holding: RemoteHolding,
+    pub id: AccountId,
+    pub status: RemoteStatus,
+}
+
+/// An ICS-27 interchain account acting for the account.
+///
+/// It is owned by the wallet's key rather than the account, so recovering the account's
+/// governance leaves it with the old key.
+#[derive(Debug, Clone)]
+pub struct InterchainAccountAudit {
+    pub host_chain: String,
+    pub connection_id: String,
+    pub owner: String,
+    /// `None` until it is registered.
+    pub address: Option<String>,
+}
+
+/// What controls an account and the remote accounts it reaches.
+#[derive(Debug, Clone)]
+pub struct Audit {
+    pub id: AccountId,
+    pub chain: String,
+    /// The account's owner then the owner of each parent, up to the top-level owner.
+    pub owners: Vec<(AccountId, GovernanceDetails<String>)>,
+    pub pending_owner: Option<GovernanceDetails<String>>,
+    /// Whether the wallet is the top-level owner, and can act for the account.
+    pub wallet_controls: bool,
+    pub remotes: Vec<RemoteAudit>,
+    pub interchain_accounts: Vec<InterchainAccountAudit>,
+}
+
+impl Audit {
+    pub fn top_level_owner(&self) -> &GovernanceDetails<String> {
+        &self.owners.last().expect("an account has an owner").1
+    }
+
+    pub fn unreachable(&self) -> Vec<&RemoteAudit> {
+        self.remotes
+            .iter()
+            .filter(|remote| remote.status != RemoteStatus::Reachable)
+            .collect()
+    }
+}
+
+/// The owners of `account` up to the top-level one, following sub-account governance.
+fn owner_chain(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+) -> IcaaResult<Vec<(AccountId, GovernanceDetails<String>)>> {
+    let mut owners = vec![(account.id()?, governance::ownership(account)?.owner)];
+    while let Some((_, GovernanceDetails::SubAccount { manager, .. })) = owners.last().cloned() {
+        if owners.len() > MAX_PARENTS {
+            return Err(IcaaError::TooManyParents {
+                account: account.id()?,
+                max: MAX_PARENTS,
+            });
+        }
+        let config: ManagerConfig = home
+            .wasm_querier()
+            .smart_query(&manager, &manager::QueryMsg::Config {})?;
+        let parent = load_account(home, config.account_id.seq())?;
+        owners.push((config.account_id, governance::ownership(&parent)?.owner));
+    }
+    Ok(owners)
+}
+
+fn wallet_controls(home: &Daemon, owners: &[(AccountId, GovernanceDetails<String>)]) -> bool {
+    matches!(
+        owners.last(),
+        Some((_, GovernanceDetails::Monarch { monarch })) if *monarch == home.sender().to_string()
+    )
+}
+
+/// Check who controls `account` and whether each of its remote accounts is owned by the
+/// ibc-host of its chain.
+///
+/// Remote accounts are bound to the home account's trace and sequence, not to a key: as long as
+/// they are reachable, whoever controls the home account controls them. `chains` maps the
+/// chains we're connected to by chain name, remote accounts elsewhere are
+/// [`RemoteStatus::Unknown`]. ICS-27 interchain accounts bound to the account in the backends file
+/// are listed too, they follow the wallet's key instead.
+pub fn audit(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    chains: &HashMap<String, Daemon>,
+) -> IcaaResult<Audit> {
+    let id = account.id()?;
+    let owners = owner_chain(home, account)?;
+    let wallet_controls = wallet_controls(home, &owners);
+
+    let home_chain = chain_name(home);
+    let remotes = sweep::scan(home, account, chains)?
+        .into_iter()
+        .map(|holding| {
+            let remote_id = sweep::remote_account_id(&home_chain, &id, &holding.path)?;
+            let chain = chains.get(&holding.path.last().unwrap().to_string());
+            let status = match (chain, &holding.proxy) {
+                (_, None) => RemoteStatus::NotRegistered,
+                (None, _) => RemoteStatus::Unknown,
+                (Some(remote), Some(_)) => {
+                    let abstr = Abstract::load_from(remote.clone())?;
+                    let ibc_host = abstr.ibc.host.addr_str()?;
+                    let remote_account = AbstractAccount::new(&abstr, remote_id.clone());
+                    match governance::ownership(&remote_account)?.owner {
+                        GovernanceDetails::External {
+                            governance_address, ..
+                        } if governance_address == ibc_host => RemoteStatus::Reachable,
+                        owner => RemoteStatus::ForeignOwner(owner),
+                    }
+                }
+            };
+            Ok(RemoteAudit {
+                holding,
+                id: remote_id,
+                status,
+            })
+        })
+        .collect::<IcaaResult<Vec<_>>>()?;
+
+    let mut interchain_accounts = vec![];
+    for host_chain in Backends::load()?.ics27_hosts(&chain_id(home)) {
+        let controller = match Controller::for_pair(home, &id, &host_chain) {
+            Ok(Some(controller)) => controller,
+            // Bound to another account, it doesn't act for this one.
+            Ok(None) | Err(IcaaError::IcaBound { .. }) => continue,
+            Err(err) => return Err(err),
+        };
+        interchain_accounts.push(InterchainAccountAudit {
+            address: controller.address()?,
+            owner: controller.owner(),
+            connection_id: controller.connection_id,
+            host_chain,
+        });
+    }
+
+    Ok(Audit {
+        pending_owner: governance::ownership(account)?.pending_owner,
+        id,
+        chain: home_chain.to_string(),
+        owners,
+        wallet_controls,
+        remotes,
+        interchain_accounts,
+    })
+}
+
+/// Send an empty packet to every reachable remote account to prove the home account still
+/// controls it.
+pub fn ping(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    audit: &Audit,
+    strategy: &WaitStrategy,
+) -> IcaaResult<()> {
+    for remote in &audit.remotes {
+        // Nested accounts are only reached through their parent remote account.
+        if remote.status != RemoteStatus::Reachable || remote.holding.path.len() > 1 {
+            continue;
+        }
+        let host_chain = remote.holding.path[0].to_string();
+        execute_remote(
+            interchain,
+            home,
+            account,
+            &host_chain,
+            &format!("recovery ping of {host_chain}"),
+            vec![],
+            strategy,
+        )?;
+        warn!("{} answered on {}", remote.id, host_chain);
+    }
+    Ok(())
+}
+
+/// Hand `account`, and with it every remote account it reaches, over to `new_owner`.
+///
+/// Remote accounts are never touched: they follow the home account, so recovering means
+/// recovering the home account while someone still controls it, then checking nothing was left
+/// behind:
+///
+/// 1. The wallet must be the top-level owner: the account's monarch, or the monarch of the parent
+///    it is a sub-account of when its own key is lost.
+/// 2. The ownership transfer to `new_owner` is proposed, e.g. the rotated key or a parent
+///    account the wallet owns.
+/// 3. When the wallet is also the top-level owner of `new_owner` the transfer is accepted right
+///    away. Otherwise the new owner accepts it with [`finish`], signing with its own key, or a
+///    multisig executes [`governance::accept_msg`].
+///
+/// Returns whether the transfer was accepted.
+pub fn recover(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    chains: &HashMap<String, Daemon>,
+    new_owner: &NewOwner,
+) -> IcaaResult<bool> {
+    let before = audit(home, account, chains)?;
+    if !before.wallet_controls {
+        return Err(IcaaError::NotControlled {
+            account: before.id,
+            owner: describe(before.top_level_owner()),
+        });
+    }
+    for remote in before.unreachable() {
+        warn!(
+            "{} on {} is already {:?}, recovery won't restore it",
+            remote.id,
+            remote.holding.path_string(),
+            remote.status
+        );
+    }
+    for ica in &before.interchain_accounts {
+        warn!(
+            "The interchain account on {} is key-bound to {}, recovery won't move it",
+            ica.host_chain, ica.owner
+        );
+    }
+
+    governance::transfer(account, new_owner, None)?;
+    let wallet = home.sender().to_string();
+    let accept_now = match new_owner {
+        NewOwner::Monarch(monarch) => *monarch == wallet,
+        NewOwner::SubAccount(parent) => {
+            wallet_controls(home, &owner_chain(home, &load_account(home, *parent)?)?)
+        }
+        NewOwner::Multisig(_) => false,
+    };
+    if accept_now {
+        governance::accept(account)?;
+    }
+    Ok(accept_now)
+}
+
+/// Accept a pending recovery with the new owner's signer, then check the remote accounts.
+pub fn finish(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    chains: &HashMap<String, Daemon>,
+) -> IcaaResult<Audit> {
+    if governance::ownership(account)?.pending_owner.is_none() {
+        return Err(IcaaError::NoPendingTransfer(account.id()?));
+    }
+    governance::accept(account)?;
+    let after = audit(home, account, chains)?;
+    if !after.wallet_controls {
+        return Err(IcaaError::NotControlled {
+            account: after.id,
+            owner: describe(after.top_level_owner()),
+        });
+    }
+    Ok(after)
+}
+
+impl fmt::Display for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Account {} on {}", self.id, self.chain)?;
+        for (id, owner) in &self.owners {
+            writeln!(f, "  {:<12} owned by {}", id.to_string(), describe(owner))?;
+        }
+        if let Some(pending) = &self.pending_owner {
+            writeln!(f, "  pending transfer to {}", describe(pending))?;
+        }
+        writeln!(
+            f,
+            "  the wallet {} the account",
+            if self.wallet_controls {
+                "controls"
+            } else {
+                "does not control"
+            }
+        )?;
+        for remote in &self.remotes {
+            let status = match &remote.status {
+                RemoteStatus::Reachable => "reachable".to_string(),
+                RemoteStatus::NotRegistered => "not registered on the IBC client".to_string(),
+                RemoteStatus::ForeignOwner(owner) => format!("owned by {}", describe(owner)),
+                RemoteStatus::Unknown => "unknown (not connected)".to_string(),
+            };
+            writeln!(
+                f,
+                "  remote {:<32} {:<24} {}",
+                remote.holding.path_string(),
+                remote.id.to_string(),
+                status
+            )?;
+        }
+        for ica in &self.interchain_accounts {
+            writeln!(
+                f,
+                "  ica    {:<32} {:<24} key-bound to {}, not recoverable by governance transfer",
+                format!("{} ({})", ica.host_chain, ica.connection_id),
+                ica.address.as_deref().unwrap_or("not registered"),
+                ica.owner
+            )?;
+        }
+        Ok(())
+    }
+}
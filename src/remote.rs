@@ -0,0 +1,374 @@
+use abstract_interface::{Abstract, AbstractAccount, ManagerExecFns};
+use abstract_std::ibc_host::{HelperAction, HostAction};
+use abstract_std::manager::ModuleInstallConfig;
+use abstract_std::objects::chain_name::ChainName;
+use abstract_std::objects::namespace::Namespace;
+use abstract_std::objects::AssetEntry;
+use abstract_std::version_control::{NamespaceResponse, QueryMsgFns as VcQueryFns};
+use abstract_std::{ibc_client, manager, proxy, PROXY};
+use cosmwasm_std::{to_json_binary, CosmosMsg};
+use cw_orch::contract::Deploy;
+use cw_orch::daemon::Daemon;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use cw_orch_interchain::types::NestedPacketsFlow;
+use log::warn;
+
+use crate::batch::{Action, Batch, StageResult};
+use crate::discovery::{ensure_host, HostRoute};
+use crate::error::{IcaaError, IcaaResult};
+use crate::wait::WaitStrategy;
+use crate::{chain_name, guard, ics27, list_remote_proxies, timeout};
+
+// Limits the remote manager enforces on `UpdateInfo`.
+const MAX_NAME_LEN: usize = 64;
+const MAX_DESCRIPTION_LEN: usize = 1024;
+const MIN_LINK_LEN: usize = 11;
+const MAX_LINK_LEN: usize = 128;
+
+/// Register a remote account on `host_chain` unless the account already has one.
+///
+/// Refuses to register when the host is not known to the home chain's IBC client or its path is
+/// down. Returns the account's remote proxies afterwards.
+///
+/// Pairs configured for ICS-27 in the backends file register the wallet's interchain account
+/// instead and only return that one, provided it is bound to `account`.
+pub fn ensure_remote_account(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    host_chain: &str,
+    strategy: &WaitStrategy,
+) -> IcaaResult<Vec<(ChainName, Option<String>)>> {
+    let target = ChainName::from_string(host_chain.to_string())?;
+    if let Some(controller) = ics27::Controller::for_pair(home, &account.id()?, host_chain)? {
+        ensure_host(home, host_chain)?;
+        return Ok(vec![(target, Some(controller.register(strategy)?))]);
+    }
+    let remote_proxies = list_remote_proxies(home, account)?;
+    if remote_proxies.iter().any(|(chain, _)| chain == &target) {
+        warn!("{} already registered on {}", target, chain_name(home));
+        return Ok(remote_proxies);
+    }
+
+    if let HostRoute::Abstract(host) = ensure_host(home, host_chain)? {
+        warn!(
+            "Registering remote account on {} through channel {:?}",
+            host.chain, host.polytone_channel
+        );
+    }
+    let remote_acc_tx = account.register_remote_account(host_chain)?;
+    timeout::wait_checked(
+        interchain,
+        home,
+        &account.id()?,
+        &format!("register remote account on {host_chain}"),
+        remote_acc_tx,
+        strategy,
+    )?;
+
+    list_remote_proxies(home, account)
+}
+
+/// Wrap a proxy message so the account owner can send it to the manager.
+pub fn exec_on_proxy(msg: &proxy::ExecuteMsg) -> anyhow::Result<manager::ExecuteMsg> {
+    Ok(manager::ExecuteMsg::ExecOnModule {
+        module_id: PROXY.to_string(),
+        exec_msg: to_json_binary(msg)?,
+    })
+}
+
+/// Manager message registering a remote account on `host_chain`.
+pub fn register_msg(
+    host_chain: &str,
+    base_asset: Option<AssetEntry>,
+    namespace: Option<String>,
+    install_modules: Vec<ModuleInstallConfig>,
+) -> anyhow::Result<manager::ExecuteMsg> {
+    exec_on_proxy(&proxy::ExecuteMsg::IbcAction {
+        msg: ibc_client::ExecuteMsg::Register {
+            host_chain: host_chain.to_string(),
+            base_asset,
+            namespace,
+            install_modules,
+        },
+    })
+}
+
+/// Builds a customized remote account, mirroring `AbstractClient::account_builder()`.
+///
+/// The `Register` packet only carries the namespace, base asset and modules. Name, description
+/// and link are applied with a follow-up `UpdateInfo` on the remote manager once the account
+/// exists. Everything is checked against the remote chain in [`Self::validate`] before sending.
+pub struct RemoteAccountBuilder {
+    host_chain: String,
+    name: Option<String>,
+    description: Option<String>,
+    link: Option<String>,
+    namespace: Option<String>,
+    base_asset: Option<AssetEntry>,
+    install_modules: Vec<ModuleInstallConfig>,
+}
+
+impl RemoteAccountBuilder {
+    pub fn new(host_chain: impl Into<String>) -> Self {
+        Self {
+            host_chain: host_chain.into(),
+            name: None,
+            description: None,
+            link: None,
+            namespace: None,
+            base_asset: None,
+            install_modules: vec![],
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn link(mut self, link: impl Into<String>) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+
+    /// Claim a namespace for the remote account on the remote chain.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    pub fn base_asset(mut self, base_asset: impl Into<AssetEntry>) -> Self {
+        self.base_asset = Some(base_asset.into());
+        self
+    }
+
+    pub fn install_module(mut self, module: ModuleInstallConfig) -> Self {
+        self.install_modules.push(module);
+        self
+    }
+
+    /// Check every option against what the remote chain supports.
+    pub fn validate(&self, remote: &Daemon) -> anyhow::Result<()> {
+        if let Some(name) = &self.name {
+            if name.is_empty() || name.len() > MAX_NAME_LEN {
+                anyhow::bail!("name must be 1 to {MAX_NAME_LEN} characters");
+            }
+        }
+        if let Some(description) = &self.description {
+            if description.is_empty() || description.len() > MAX_DESCRIPTION_LEN {
+                anyhow::bail!("description must be 1 to {MAX_DESCRIPTION_LEN} characters");
+            }
+        }
+        if let Some(link) = &self.link {
+            if !(MIN_LINK_LEN..=MAX_LINK_LEN).contains(&link.len())
+                || !["http://", "https://", "ipfs://"]
+                    .iter()
+                    .any(|scheme| link.starts_with(scheme))
+            {
+                anyhow::bail!(
+                    "link must be an http(s) or ipfs url of {MIN_LINK_LEN} to {MAX_LINK_LEN} characters"
+                );
+            }
+        }
+
+        let remote_abstr = Abstract::load_from(remote.clone())?;
+        if let Some(namespace) = &self.namespace {
+            let namespace = Namespace::new(namespace)?;
+            if let Some(fee) = remote_abstr
+                .version_control
+                .config()?
+                .namespace_registration_fee
+            {
+                anyhow::bail!(
+                    "{} charges {} to claim a namespace, which can't be paid over IBC",
+                    chain_name(remote),
+                    fee
+                );
+            }
+            if let NamespaceResponse::Claimed(info) =
+                remote_abstr.version_control.namespace(namespace.clone())?
+            {
+                anyhow::bail!(
+                    "namespace {} is already claimed on {} by {}",
+                    namespace,
+                    chain_name(remote),
+                    info.account_id
+                );
+            }
+        }
+        if let Some(base_asset) = &self.base_asset {
+            remote_abstr.ans_host.resolve(base_asset).map_err(|e| {
+                anyhow::anyhow!(
+                    "base asset {} is not in the ANS of {}: {}",
+                    base_asset,
+                    chain_name(remote),
+                    e
+                )
+            })?;
+        }
+        for module in &self.install_modules {
+            remote_abstr
+                .version_control
+                .modules(vec![module.module.clone()])
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "module {} is not available on {}: {}",
+                        module.module,
+                        chain_name(remote),
+                        e
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    /// The registration and, if needed, the info update as a [`Batch`].
+    pub fn into_batch(self) -> Batch {
+        let batch = Batch::new().push(Action::RegisterRemote {
+            host_chain: self.host_chain.clone(),
+            base_asset: self.base_asset,
+            namespace: self.namespace,
+            install_modules: self.install_modules,
+        });
+        if self.name.is_none() && self.description.is_none() && self.link.is_none() {
+            return batch;
+        }
+        batch.remote_dispatch(
+            self.host_chain,
+            vec![manager::ExecuteMsg::UpdateInfo {
+                name: self.name,
+                description: self.description,
+                link: self.link,
+            }],
+        )
+    }
+
+    /// Validate against `remote`, then register and customize the remote account.
+    pub fn build(
+        self,
+        interchain: &DaemonInterchainEnv,
+        home: &Daemon,
+        remote: &Daemon,
+        account: &AbstractAccount<Daemon>,
+        strategy: &WaitStrategy,
+    ) -> IcaaResult<Vec<StageResult>> {
+        let target = ChainName::from_string(self.host_chain.clone())?;
+        if list_remote_proxies(home, account)?
+            .iter()
+            .any(|(chain, _)| chain == &target)
+        {
+            return Err(IcaaError::RemoteAccountExists {
+                account: account.id()?,
+                host_chain: target.to_string(),
+            });
+        }
+        if let HostRoute::Ics27 { .. } = ensure_host(home, &self.host_chain)? {
+            return Err(anyhow::anyhow!(
+                "{} is reached over ICS-27, only Abstract remote accounts can be customized",
+                target
+            )
+            .into());
+        }
+        self.validate(remote)?;
+        self.into_batch()
+            .submit(interchain, home, account, strategy)
+    }
+}
+
+/// Address of the proxy of `account` on `host_chain`, as known by the home chain's IBC client.
+///
+/// For pairs configured for ICS-27 this is the address of the wallet's interchain account.
+pub fn remote_proxy(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    host_chain: &str,
+) -> IcaaResult<String> {
+    if let Some(controller) = ics27::Controller::for_pair(home, &account.id()?, host_chain)? {
+        return controller.ensure_address();
+    }
+    let proxy = list_remote_proxies(home, account)?
+        .into_iter()
+        .find(|(chain, _)| chain.to_string() == host_chain)
+        .and_then(|(_, proxy)| proxy);
+    match proxy {
+        Some(proxy) => Ok(proxy),
+        None => Err(IcaaError::RemoteAccountMissing {
+            account: account.id()?,
+            host_chain: host_chain.to_string(),
+        }),
+    }
+}
+
+/// Execute `msgs` from the proxy of `account`'s remote account on `host_chain` and wait for them.
+///
+/// The contracts called are checked against the mainnet guard's allowlist first. Pairs configured
+/// for ICS-27 send them from the wallet's interchain account instead.
+pub fn execute_remote(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    host_chain: &str,
+    step: &str,
+    msgs: Vec<CosmosMsg>,
+    strategy: &WaitStrategy,
+) -> IcaaResult<NestedPacketsFlow<Daemon>> {
+    guard::check_contracts(home, &msgs)?;
+    if let Some(controller) = ics27::Controller::for_pair(home, &account.id()?, host_chain)? {
+        return controller.execute(interchain, step, msgs, strategy);
+    }
+    let tx = account.manager.execute_on_module(
+        PROXY,
+        proxy::ExecuteMsg::IbcAction {
+            msg: ibc_client::ExecuteMsg::RemoteAction {
+                host_chain: host_chain.to_string(),
+                action: HostAction::Dispatch {
+                    manager_msgs: vec![exec_on_proxy(&proxy::ExecuteMsg::ModuleAction { msgs })?],
+                },
+            },
+        },
+    )?;
+    timeout::wait_checked(interchain, home, &account.id()?, step, tx, strategy)
+}
+
+/// Ask the remote account on `host_chain` to send all its funds back home and wait for them.
+///
+/// Fails with [`IcaaError::RemoteAccountMissing`] when the account has no remote account there.
+pub fn request_all_back(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    host_chain: &str,
+    strategy: &WaitStrategy,
+) -> IcaaResult<NestedPacketsFlow<Daemon>> {
+    if ics27::Controller::for_pair(home, &account.id()?, host_chain)?.is_some() {
+        return Err(anyhow::anyhow!(
+            "{host_chain} is reached over ICS-27, which has no send-all-back helper"
+        )
+        .into());
+    }
+    remote_proxy(home, account, host_chain)?;
+    warn!("Requesting all funds back from {}", host_chain);
+    let tx = account.manager.execute_on_module(
+        PROXY,
+        proxy::ExecuteMsg::IbcAction {
+            msg: ibc_client::ExecuteMsg::RemoteAction {
+                host_chain: host_chain.to_string(),
+                action: HostAction::Helpers(HelperAction::SendAllBack),
+            },
+        },
+    )?;
+    timeout::wait_checked(
+        interchain,
+        home,
+        &account.id()?,
+        &format!("request funds back from {host_chain}"),
+        tx,
+        strategy,
+    )
+}
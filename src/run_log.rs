@@ -0,0 +1,271 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cosmwasm_std::Uint128;
+use cw_orch::daemon::{CosmTxResponse, Daemon};
+use cw_orch_interchain::types::{IbcPacketAckDecode, IbcPacketOutcome, NestedPacketsFlow};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use pretty_env_logger::env_logger;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::chain_id;
+
+/// Directory the JSON run logs are written to.
+pub const LOG_DIR: &str = "logs";
+/// Set to write a JSON run log next to the console output.
+pub const JSON_LOG_ENV: &str = "ICAA_JSON_LOG";
+
+static RUN_LOG: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// A structured event of a run, written as one JSON line of the run log.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A transaction included on `chain`.
+    Tx {
+        step: String,
+        chain: String,
+        account: Option<String>,
+        txhash: String,
+        height: u64,
+        gas_used: u64,
+    },
+    /// A packet sent by `txhash`.
+    PacketSent {
+        step: String,
+        chain: String,
+        txhash: String,
+        port: String,
+        channel: String,
+        sequence: String,
+    },
+    /// The outcome of a packet, `depth` hops away from the home transaction.
+    PacketDone {
+        step: String,
+        src_chain: String,
+        dest_chain: Option<String>,
+        depth: usize,
+        outcome: &'static str,
+        error: Option<String>,
+    },
+    /// A balance checked before spending it.
+    Balance {
+        chain: String,
+        holder: String,
+        asset: String,
+        amount: String,
+    },
+    /// The error that ended the run.
+    Failed { class: String, message: String },
+}
+
+/// Writes every log record to the console like `env_logger` does, and to the run log if enabled.
+struct RunLogger {
+    console: env_logger::Logger,
+}
+
+impl Log for RunLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata) || (enabled() && metadata.level() <= Level::Info)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.console.matches(record) {
+            self.console.log(record);
+        }
+        if record.level() <= Level::Info {
+            write(json!({
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            }));
+        }
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+    }
+}
+
+/// Install the logger. With [`JSON_LOG_ENV`] set, info and above also go to a new timestamped
+/// file in [`LOG_DIR`], named after the running binary.
+pub fn init() -> anyhow::Result<()> {
+    let console = env_logger::Builder::from_default_env().build();
+    let mut max_level = console.filter();
+    if std::env::var_os(JSON_LOG_ENV).is_some() {
+        let path = open()?;
+        max_level = max_level.max(LevelFilter::Info);
+        eprintln!("Writing the run log to {}", path.display());
+    }
+    log::set_boxed_logger(Box::new(RunLogger { console }))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+fn open() -> anyhow::Result<PathBuf> {
+    let program = std::env::args()
+        .next()
+        .as_deref()
+        .and_then(|arg| Path::new(arg).file_stem()?.to_str().map(String::from))
+        .unwrap_or_else(|| "icaa".into());
+    fs::create_dir_all(LOG_DIR)?;
+    let path = Path::new(LOG_DIR).join(format!("{}-{}.jsonl", program, now_ms()));
+    let _ = RUN_LOG.set(Mutex::new(File::create(&path)?));
+    Ok(path)
+}
+
+/// Whether a run log is being written.
+pub fn enabled() -> bool {
+    RUN_LOG.get().is_some()
+}
+
+fn now_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default()
+}
+
+fn write(mut entry: serde_json::Value) {
+    let Some(file) = RUN_LOG.get() else {
+        return;
+    };
+    entry["ts_ms"] = json!(now_ms());
+    if let Ok(mut file) = file.lock() {
+        // Losing a line of the run log isn't worth failing the run over.
+        let _ = writeln!(file, "{}", entry);
+    }
+}
+
+/// Write `event` to the run log, if enabled.
+pub fn event(event: Event) {
+    if enabled() {
+        write(serde_json::to_value(event).unwrap_or_default());
+    }
+}
+
+/// Record the balance of `asset` held by `holder` on `chain`.
+pub fn balance(chain: &Daemon, holder: impl ToString, asset: impl ToString, amount: Uint128) {
+    event(Event::Balance {
+        chain: chain_id(chain),
+        holder: holder.to_string(),
+        asset: asset.to_string(),
+        amount: amount.to_string(),
+    });
+}
+
+/// Record a transaction of `step` on `chain` and the packets it sent.
+pub fn tx(step: &str, chain: &Daemon, account: Option<String>, tx: &CosmTxResponse) {
+    if enabled() {
+        tx_events(step, &chain_id(chain), account, tx)
+            .into_iter()
+            .for_each(event);
+    }
+}
+
+/// Record every packet that followed the home transaction of `flow`, with the relayer txs
+/// delivering them.
+///
+/// The home transaction itself is recorded by [`tx`] when it's submitted.
+pub fn flow(step: &str, flow: &NestedPacketsFlow<Daemon>) {
+    if enabled() {
+        packet_events(step, flow, 1).into_iter().for_each(event);
+    }
+}
+
+fn tx_events(step: &str, chain: &str, account: Option<String>, tx: &CosmTxResponse) -> Vec<Event> {
+    let mut events = vec![Event::Tx {
+        step: step.to_string(),
+        chain: chain.to_string(),
+        account,
+        txhash: tx.txhash.clone(),
+        height: tx.height,
+        gas_used: tx.gas_used,
+    }];
+    for packet in tx.get_events("send_packet") {
+        let attr = |key| packet.get_first_attribute_value(key).unwrap_or_default();
+        events.push(Event::PacketSent {
+            step: step.to_string(),
+            chain: chain.to_string(),
+            txhash: tx.txhash.clone(),
+            port: attr("packet_src_port"),
+            channel: attr("packet_src_channel"),
+            sequence: attr("packet_sequence"),
+        });
+    }
+    events
+}
+
+fn packet_events(step: &str, flow: &NestedPacketsFlow<Daemon>, depth: usize) -> Vec<Event> {
+    let mut events = vec![];
+    for packet in &flow.packets {
+        let done = |dest_chain, outcome, error| Event::PacketDone {
+            step: step.to_string(),
+            src_chain: flow.tx_id.chain_id.clone(),
+            dest_chain,
+            depth,
+            outcome,
+            error,
+        };
+        match &packet.outcome {
+            IbcPacketOutcome::Timeout { .. } => events.push(done(None, "timeout", None)),
+            IbcPacketOutcome::Success {
+                receive_tx, ack, ..
+            } => {
+                let dest_chain = Some(receive_tx.tx_id.chain_id.clone());
+                events.push(match ack {
+                    IbcPacketAckDecode::Error(error) => {
+                        done(dest_chain, "error_ack", Some(error.clone()))
+                    }
+                    _ => done(dest_chain, "success", None),
+                });
+                events.extend(tx_events(
+                    step,
+                    &receive_tx.tx_id.chain_id,
+                    None,
+                    &receive_tx.tx_id.response,
+                ));
+                events.extend(packet_events(step, receive_tx, depth + 1));
+            }
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use cw_orch_interchain::types::TxId;
+
+    use super::*;
+
+    #[test]
+    fn submitted_and_awaited_tx_is_logged_once() {
+        let response = CosmTxResponse {
+            txhash: "AAAA".to_string(),
+            height: 42,
+            gas_used: 150_000,
+            ..Default::default()
+        };
+        let flow = NestedPacketsFlow::<Daemon> {
+            tx_id: TxId {
+                chain_id: "juno-1".to_string(),
+                response: response.clone(),
+            },
+            packets: vec![],
+        };
+
+        // What a step writes: the tx when it's submitted, then the flow once awaited.
+        let mut events = tx_events("send funds", "juno-1", None, &response);
+        events.extend(packet_events("send funds", &flow, 1));
+
+        let txs = events
+            .iter()
+            .filter(|event| matches!(event, Event::Tx { txhash, .. } if txhash == "AAAA"))
+            .count();
+        assert_eq!(txs, 1);
+    }
+}
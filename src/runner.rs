@@ -0,0 +1,370 @@
+use std::fmt;
+
+use cw_orch::daemon::DaemonError;
+use log::error;
+use tokio::runtime::Runtime;
+
+use crate::error::IcaaError;
+use crate::guard::Aborted;
+use crate::run_log::{self, Event};
+use crate::timeout::TimeoutError;
+use crate::wait::{DeadlineExceeded, PENDING_FILE};
+
+/// What kind of failure ended a run, each with its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Anything not classified below.
+    Other,
+    /// Missing or invalid env, signer, guard or chain configuration.
+    Configuration,
+    /// A node couldn't be reached or didn't answer.
+    Network,
+    /// A chain rejected a transaction.
+    ChainRejected,
+    /// An IBC packet failed, timed out or wasn't relayed in time.
+    Ibc,
+    /// The user declined a confirmation or interrupted the run.
+    Aborted,
+}
+
+impl ErrorClass {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorClass::Other => 1,
+            ErrorClass::Configuration => 2,
+            ErrorClass::Network => 3,
+            ErrorClass::ChainRejected => 4,
+            ErrorClass::Ibc => 5,
+            ErrorClass::Aborted => 130,
+        }
+    }
+
+    /// Classify `err` by the first cause we recognize.
+    pub fn of(err: &anyhow::Error) -> Self {
+        for cause in err.chain() {
+            if cause.is::<Aborted>() {
+                return ErrorClass::Aborted;
+            }
+            if let Some(err) = cause.downcast_ref::<IcaaError>() {
+                return match err {
+                    IcaaError::IbcClientMissing(_)
+                    | IcaaError::ModuleMissing { .. }
+                    | IcaaError::RemoteAccountMissing { .. }
+                    | IcaaError::InterchainAccountMissing { .. }
+                    | IcaaError::RemoteAccountExists { .. }
+                    | IcaaError::NotControlled { .. }
+                    | IcaaError::NotSubAccount { .. }
+                    | IcaaError::NoPendingTransfer(_)
+                    | IcaaError::TooManyParents { .. }
+                    | IcaaError::UnknownNetwork(_)
+                    | IcaaError::UnknownHost { .. }
+                    | IcaaError::IcaBound { .. }
+                    | IcaaError::AnsResolution { .. }
+                    | IcaaError::NonNativeAsset { .. }
+                    | IcaaError::UnsupportedAsset(_)
+                    | IcaaError::UnknownDecimals { .. }
+                    | IcaaError::InvalidAmount(_)
+                    | IcaaError::InvalidAccountRef(_) => ErrorClass::Configuration,
+                    IcaaError::Timeout(_)
+                    | IcaaError::PathClosed { .. }
+                    | IcaaError::ErrorAck { .. }
+                    | IcaaError::IcaPacket { .. } => ErrorClass::Ibc,
+                    IcaaError::Daemon(err) => Self::of_daemon(err),
+                    // Transparent variants skip the wrapped error itself in `chain()`.
+                    IcaaError::Other(inner) => Self::of(inner),
+                    IcaaError::CwOrch(_)
+                    | IcaaError::Abstract(_)
+                    | IcaaError::AbstractStd(_)
+                    | IcaaError::Std(_) => continue,
+                    IcaaError::EmptyBalance { .. }
+                    | IcaaError::InsufficientBalance { .. }
+                    | IcaaError::Io(_) => ErrorClass::Other,
+                };
+            }
+            if cause.is::<TimeoutError>() || cause.is::<DeadlineExceeded>() {
+                return ErrorClass::Ibc;
+            }
+            if cause.is::<std::env::VarError>() || cause.is::<serde_json::Error>() {
+                return ErrorClass::Configuration;
+            }
+            if let Some(err) = cause.downcast_ref::<DaemonError>() {
+                return Self::of_daemon(err);
+            }
+        }
+        ErrorClass::Other
+    }
+
+    fn of_daemon(err: &DaemonError) -> Self {
+        match err {
+            DaemonError::TxFailed { .. } => ErrorClass::ChainRejected,
+            DaemonError::Status(_)
+            | DaemonError::TonicTransport(_)
+            | DaemonError::CannotConnectGRPC => ErrorClass::Network,
+            _ => ErrorClass::Other,
+        }
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorClass::Other => write!(f, "error"),
+            ErrorClass::Configuration => write!(f, "configuration error"),
+            ErrorClass::Network => write!(f, "network error"),
+            ErrorClass::ChainRejected => write!(f, "transaction rejected"),
+            ErrorClass::Ibc => write!(f, "IBC error"),
+            ErrorClass::Aborted => write!(f, "aborted"),
+        }
+    }
+}
+
+/// What the user can do about `err`, if we know.
+pub fn hint(class: ErrorClass, err: &anyhow::Error) -> Option<String> {
+    let message = format!("{:#}", err).to_lowercase();
+    let hint = match class {
+        _ if message.contains("already exists") || message.contains("already registered") => {
+            "the remote account already exists, `icaa provision` reuses existing remote accounts"
+        }
+        ErrorClass::Configuration => match err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<IcaaError>())
+        {
+            Some(IcaaError::IbcClientMissing(_)) => {
+                "enable IBC on the account first, `icaa bootstrap` does it"
+            }
+            Some(IcaaError::ModuleMissing { .. }) => "install the module on the account first",
+            Some(IcaaError::RemoteAccountMissing { .. }) => {
+                "create the remote account first with `icaa provision`"
+            }
+            Some(IcaaError::InterchainAccountMissing { .. }) => {
+                "register the interchain account first with `icaa provision`"
+            }
+            Some(IcaaError::UnknownNetwork(_) | IcaaError::UnknownHost { .. }) => {
+                "check the chain name, `icaa hosts` lists the remote hosts"
+            }
+            Some(IcaaError::RemoteAccountExists { .. }) => {
+                "the remote account exists, act on it instead of creating it"
+            }
+            Some(IcaaError::IcaBound { .. }) => {
+                "the wallet has one interchain account per connection, see the account bound to it in backends.json"
+            }
+            Some(IcaaError::NotControlled { .. }) => {
+                "use the owner's signer, or recover from a parent account the wallet owns"
+            }
+            Some(IcaaError::NotSubAccount { .. }) => {
+                "`icaa sub-accounts --parent <seq> list` lists the parent's sub-accounts"
+            }
+            Some(IcaaError::UnknownDecimals { .. }) => {
+                "add the denom's decimals to decimals.json, e.g. `{ \"juno-1\": { \"ujuno\": 6 } }`"
+            }
+            Some(IcaaError::AnsResolution { .. }) => "check the asset name, e.g. `juno>juno`",
+            Some(IcaaError::InvalidAccountRef(_)) => {
+                "give the account's sequence, e.g. `7`, or the namespace it claimed"
+            }
+            _ => "check .env, signers.json and guard.json",
+        },
+        ErrorClass::Network => "check your connection and the chain's gRPC endpoints",
+        ErrorClass::ChainRejected if message.contains("out of gas") => {
+            "the transaction ran out of gas, raise the gas adjustment and retry"
+        }
+        ErrorClass::ChainRejected if message.contains("insufficient") => {
+            "the wallet or account can't cover the amount and fees, top it up first"
+        }
+        ErrorClass::Ibc if message.contains("resume with") => return None,
+        ErrorClass::Ibc => "check that relayers are running on the channels used",
+        _ => return None,
+    };
+    Some(hint.to_string())
+}
+
+/// Set up env, logging, a runtime and Ctrl-C handling, run `main` and exit with its error class.
+pub fn run(main: impl FnOnce(&Runtime) -> anyhow::Result<()>) -> ! {
+    dotenv::dotenv().ok();
+    if let Err(err) = run_log::init() {
+        eprintln!("failed to set up logging: {}", err);
+        std::process::exit(ErrorClass::Configuration.exit_code());
+    }
+
+    let rt = match Runtime::new() {
+        Ok(rt) => rt,
+        Err(err) => {
+            error!("failed to start the runtime: {}", err);
+            std::process::exit(ErrorClass::Other.exit_code());
+        }
+    };
+    rt.spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            error!(
+                "interrupted, detached IBC waits are listed in {}",
+                PENDING_FILE
+            );
+            std::process::exit(ErrorClass::Aborted.exit_code());
+        }
+    });
+
+    let Err(err) = main(&rt) else {
+        std::process::exit(0);
+    };
+    let class = ErrorClass::of(&err);
+    run_log::event(Event::Failed {
+        class: class.to_string(),
+        message: format!("{:#}", err),
+    });
+    error!("{}: {}", class, err);
+    err.chain()
+        .skip(1)
+        .for_each(|cause| error!("because: {}", cause));
+    if let Some(hint) = hint(class, &err) {
+        error!("hint: {}", hint);
+    }
+    std::process::exit(class.exit_code());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use abstract_std::objects::AccountId;
+
+    use super::*;
+    use crate::wait::PendingWait;
+
+    fn account() -> AccountId {
+        AccountId::local(1)
+    }
+
+    fn tx_failed(reason: &str) -> anyhow::Error {
+        IcaaError::Daemon(DaemonError::TxFailed {
+            code: 11,
+            reason: reason.to_string(),
+        })
+        .into()
+    }
+
+    #[test]
+    fn classes_and_hints() {
+        let deadline = DeadlineExceeded {
+            pending: PendingWait {
+                step: "send".to_string(),
+                chain_id: "juno-1".to_string(),
+                txhash: "ABC".to_string(),
+                height: 1,
+            },
+            waited: Duration::from_secs(60),
+        };
+        let cases: Vec<(anyhow::Error, ErrorClass, Option<&str>)> = vec![
+            (
+                IcaaError::IbcClientMissing(account()).into(),
+                ErrorClass::Configuration,
+                Some("icaa bootstrap"),
+            ),
+            (
+                IcaaError::UnknownDecimals {
+                    chain: "juno-1".to_string(),
+                    denom: "ujuno".to_string(),
+                }
+                .into(),
+                ErrorClass::Configuration,
+                Some("decimals.json"),
+            ),
+            (
+                IcaaError::InvalidAmount("abc".to_string()).into(),
+                ErrorClass::Configuration,
+                Some("guard.json"),
+            ),
+            (
+                anyhow::Error::from(std::env::VarError::NotPresent).context("reading the env"),
+                ErrorClass::Configuration,
+                Some(".env"),
+            ),
+            (
+                IcaaError::Daemon(DaemonError::CannotConnectGRPC).into(),
+                ErrorClass::Network,
+                Some("gRPC endpoints"),
+            ),
+            (
+                tx_failed("out of gas in location: wasm"),
+                ErrorClass::ChainRejected,
+                Some("gas adjustment"),
+            ),
+            (
+                tx_failed("insufficient funds"),
+                ErrorClass::ChainRejected,
+                Some("top it up"),
+            ),
+            (tx_failed("unauthorized"), ErrorClass::ChainRejected, None),
+            (
+                IcaaError::PathClosed {
+                    home: "juno".to_string(),
+                    host: "osmosis".to_string(),
+                    path: "channel-1".to_string(),
+                }
+                .into(),
+                ErrorClass::Ibc,
+                Some("relayers"),
+            ),
+            (deadline.into(), ErrorClass::Ibc, None),
+            (
+                anyhow::Error::from(Aborted("send".to_string())).context("sending"),
+                ErrorClass::Aborted,
+                None,
+            ),
+            (
+                IcaaError::RemoteAccountExists {
+                    account: account(),
+                    host_chain: "osmosis".to_string(),
+                }
+                .into(),
+                ErrorClass::Configuration,
+                Some("act on it"),
+            ),
+            (
+                IcaaError::EmptyBalance {
+                    holder: "juno1holder".to_string(),
+                    asset: "ujuno".to_string(),
+                }
+                .into(),
+                ErrorClass::Other,
+                None,
+            ),
+            (anyhow::anyhow!("something else"), ErrorClass::Other, None),
+        ];
+        for (err, class, expected) in cases {
+            assert_eq!(ErrorClass::of(&err), class, "class of {err:#}");
+            let hint = hint(class, &err);
+            match expected {
+                Some(expected) => assert!(
+                    hint.as_deref().is_some_and(|hint| hint.contains(expected)),
+                    "hint of {err:#}: {hint:?}"
+                ),
+                None => assert_eq!(hint, None, "hint of {err:#}"),
+            }
+        }
+    }
+
+    #[test]
+    fn wrapped_errors_keep_their_class() {
+        let err: anyhow::Error = IcaaError::Other(
+            anyhow::Error::from(IcaaError::Daemon(DaemonError::CannotConnectGRPC))
+                .context("querying balances"),
+        )
+        .into();
+        assert_eq!(ErrorClass::of(&err), ErrorClass::Network);
+    }
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let classes = [
+            ErrorClass::Other,
+            ErrorClass::Configuration,
+            ErrorClass::Network,
+            ErrorClass::ChainRejected,
+            ErrorClass::Ibc,
+            ErrorClass::Aborted,
+        ];
+        let codes: std::collections::BTreeSet<_> =
+            classes.iter().map(|class| class.exit_code()).collect();
+        assert_eq!(codes.len(), classes.len());
+        assert!(!codes.contains(&0));
+    }
+}
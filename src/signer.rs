@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use cw_orch::environment::{ChainInfo, ChainKind};
+use cw_orch::prelude::*;
+use cw_orch_interchain::prelude::{ChannelCreationValidator, DaemonInterchainEnv, InterchainEnv};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Runtime;
+
+/// Path of the signer profiles, defaults to [`DEFAULT_SIGNERS_FILE`].
+pub const SIGNERS_FILE_ENV: &str = "ICAA_SIGNERS";
+pub const DEFAULT_SIGNERS_FILE: &str = "signers.json";
+/// Profile to sign with on every chain, overriding the per-chain profiles.
+pub const PROFILE_ENV: &str = "ICAA_SIGNER";
+/// Keystore password, prompted for when unset.
+pub const KEYSTORE_PASSWORD_ENV: &str = "ICAA_KEYSTORE_PASSWORD";
+/// Set to connect without any of our keys, for commands that only query.
+pub const READ_ONLY_ENV: &str = "ICAA_READ_ONLY";
+
+/// The well-known BIP-39 test mnemonic, used as sender when connecting read-only. Nothing is ever
+/// signed with it.
+const QUERY_ONLY_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon \
+    abandon abandon abandon about";
+
+/// Where the mnemonic of a profile comes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Name of an environment variable (or `.env` entry) holding the mnemonic.
+    Env(String),
+    /// An encrypted keystore file created with [`encrypt_mnemonic`].
+    Keystore(PathBuf),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub key: KeySource,
+    /// Refuse to sign on mainnet with this profile.
+    #[serde(default)]
+    pub test_only: bool,
+}
+
+/// Signer profiles and which profile signs on which chain, read from the signers file:
+///
+/// ```json
+/// {
+///   "profiles": {
+///     "test": { "key": { "env": "TEST_MNEMONIC" }, "test_only": true },
+///     "ops": { "key": { "keystore": "keys/ops.json" } }
+///   },
+///   "chains": { "juno-1": "ops", "default": "test" }
+/// }
+/// ```
+///
+/// Without a signers file, or for chains without a profile, cw-orch picks the mnemonic from the
+/// environment as before.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Signers {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Chain id (or `default`) to profile name.
+    #[serde(default)]
+    pub chains: BTreeMap<String, String>,
+}
+
+impl Signers {
+    pub fn load() -> anyhow::Result<Self> {
+        let path =
+            std::env::var(SIGNERS_FILE_ENV).unwrap_or_else(|_| DEFAULT_SIGNERS_FILE.to_string());
+        if !Path::new(&path).exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("invalid signers file {path}: {e}"))
+    }
+
+    /// The profile signing on `chain_id`: the one selected with [`PROFILE_ENV`], else the chain's.
+    pub fn profile_for(&self, chain_id: &str) -> anyhow::Result<Option<(String, &Profile)>> {
+        let name = std::env::var(PROFILE_ENV)
+            .ok()
+            .or_else(|| self.chains.get(chain_id).cloned())
+            .or_else(|| self.chains.get("default").cloned());
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        let profile = self
+            .profiles
+            .get(&name)
+            .ok_or_else(|| anyhow::anyhow!("unknown signer profile {name}"))?;
+        Ok(Some((name, profile)))
+    }
+
+    /// The chain with the mnemonic of its profile, refusing test-only profiles on mainnet.
+    pub fn entry(&self, info: ChainInfo) -> anyhow::Result<(ChainInfo, Option<String>)> {
+        let Some((name, profile)) = self.profile_for(info.chain_id)? else {
+            return Ok((info, None));
+        };
+        if profile.test_only && info.kind == ChainKind::Mainnet {
+            anyhow::bail!(
+                "signer profile {} is test-only, refusing to sign on mainnet {}",
+                name,
+                info.chain_id
+            );
+        }
+        let mnemonic = match &profile.key {
+            KeySource::Env(var) => std::env::var(var)
+                .map_err(|_| anyhow::anyhow!("{var} is not set for signer profile {name}"))?,
+            KeySource::Keystore(path) => decrypt_keystore(path, &keystore_password(path)?)?,
+        };
+        Ok((info, Some(mnemonic)))
+    }
+}
+
+/// Connect to `chains` with their signer profiles and log the address signing on each of them.
+///
+/// With [`READ_ONLY_ENV`] set no key is loaded at all, so no funded signer is needed.
+pub fn connect_chains(rt: &Runtime, chains: Vec<ChainInfo>) -> anyhow::Result<DaemonInterchainEnv> {
+    if std::env::var_os(READ_ONLY_ENV).is_some() {
+        let entries = chains
+            .into_iter()
+            .map(|info| (info, Some(QUERY_ONLY_MNEMONIC.to_string())))
+            .collect::<Vec<_>>();
+        warn!("Connecting read-only, transactions can't be signed");
+        return Ok(DaemonInterchainEnv::new(
+            rt.handle(),
+            entries,
+            &ChannelCreationValidator,
+        )?);
+    }
+    let signers = Signers::load()?;
+    let chain_ids: Vec<&str> = chains.iter().map(|info| info.chain_id).collect();
+    let entries = chains
+        .iter()
+        .map(|info| signers.entry(info.clone()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let interchain = DaemonInterchainEnv::new(rt.handle(), entries, &ChannelCreationValidator)?;
+    for chain_id in chain_ids {
+        let profile = signers
+            .profile_for(chain_id)?
+            .map(|(name, _)| name)
+            .unwrap_or_else(|| "environment".into());
+        warn!(
+            "Signing on {} as {} ({})",
+            chain_id,
+            interchain.chain(chain_id)?.sender(),
+            profile
+        );
+    }
+    Ok(interchain)
+}
+
+/// Length of an AES-256-GCM nonce.
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Keystore {
+    /// Hex encoded scrypt salt.
+    salt: String,
+    /// Hex encoded AES-256-GCM nonce.
+    nonce: String,
+    ciphertext: String,
+}
+
+fn keystore_key(password: &str, salt: &[u8]) -> anyhow::Result<Key<Aes256Gcm>> {
+    let mut key = Key::<Aes256Gcm>::default();
+    scrypt::scrypt(
+        password.as_bytes(),
+        salt,
+        &scrypt::Params::recommended(),
+        &mut key,
+    )
+    .map_err(|e| anyhow::anyhow!("failed to derive keystore key: {e}"))?;
+    Ok(key)
+}
+
+/// Encrypt `mnemonic` with `password` into the JSON content of a keystore file.
+pub fn encrypt_mnemonic(mnemonic: &str, password: &str) -> anyhow::Result<String> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(&keystore_key(password, &salt)?)
+        .encrypt(&nonce, mnemonic.trim().as_bytes())
+        .map_err(|_| anyhow::anyhow!("failed to encrypt mnemonic"))?;
+    Ok(serde_json::to_string_pretty(&Keystore {
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })?)
+}
+
+/// Decrypt the mnemonic of the keystore file at `path`.
+pub fn decrypt_keystore(path: &Path, password: &str) -> anyhow::Result<String> {
+    let keystore: Keystore = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| anyhow::anyhow!("invalid keystore {}: {e}", path.display()))?;
+    let nonce = hex::decode(&keystore.nonce)?;
+    if nonce.len() != NONCE_LEN {
+        anyhow::bail!(
+            "invalid keystore {}: the nonce is {} bytes instead of {}",
+            path.display(),
+            nonce.len(),
+            NONCE_LEN
+        );
+    }
+    let mnemonic = Aes256Gcm::new(&keystore_key(password, &hex::decode(&keystore.salt)?)?)
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            hex::decode(&keystore.ciphertext)?.as_slice(),
+        )
+        .map_err(|_| anyhow::anyhow!("wrong password for keystore {}", path.display()))?;
+    Ok(String::from_utf8(mnemonic)?)
+}
+
+fn keystore_password(path: &Path) -> anyhow::Result<String> {
+    match std::env::var(KEYSTORE_PASSWORD_ENV) {
+        Ok(password) => Ok(password),
+        Err(_) => Ok(rpassword::prompt_password(format!(
+            "Password for {}: ",
+            path.display()
+        ))?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::JUNO_1;
+
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+        abandon abandon abandon about";
+
+    fn keystore_file(content: &str) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), content).unwrap();
+        file
+    }
+
+    #[test]
+    fn keystore_roundtrip() {
+        let file = keystore_file(&encrypt_mnemonic(MNEMONIC, "hunter2").unwrap());
+        assert_eq!(decrypt_keystore(file.path(), "hunter2").unwrap(), MNEMONIC);
+        let err = decrypt_keystore(file.path(), "hunter3").unwrap_err();
+        assert!(err.to_string().contains("wrong password"));
+    }
+
+    #[test]
+    fn rejects_a_nonce_of_the_wrong_length() {
+        let mut keystore: Keystore =
+            serde_json::from_str(&encrypt_mnemonic(MNEMONIC, "hunter2").unwrap()).unwrap();
+        keystore.nonce = hex::encode([0u8; 8]);
+        let file = keystore_file(&serde_json::to_string(&keystore).unwrap());
+        let err = decrypt_keystore(file.path(), "hunter2").unwrap_err();
+        assert!(err.to_string().contains("nonce is 8 bytes"));
+    }
+
+    #[test]
+    fn test_only_profiles_refuse_mainnet() {
+        let signers: Signers = serde_json::from_str(
+            r#"{
+                "profiles": { "test": { "key": { "env": "TEST_MNEMONIC" }, "test_only": true } },
+                "chains": { "default": "test" }
+            }"#,
+        )
+        .unwrap();
+        let (name, profile) = signers.profile_for("juno-1").unwrap().unwrap();
+        assert_eq!(name, "test");
+        assert!(profile.test_only);
+        let err = signers.entry(JUNO_1).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("refusing to sign on mainnet juno-1"));
+    }
+
+    #[test]
+    fn unknown_profiles_are_errors() {
+        let signers: Signers =
+            serde_json::from_str(r#"{ "chains": { "juno-1": "ops" } }"#).unwrap();
+        assert!(signers.profile_for("juno-1").is_err());
+        assert!(signers.profile_for("osmosis-1").unwrap().is_none());
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use abstract_client::{AbstractClient, AccountSource};
+use abstract_interface::{Abstract, AbstractAccount};
+use abstract_std::manager::{self, InfoResponse, SubAccountIdsResponse};
+use abstract_std::objects::chain_name::ChainName;
+use abstract_std::objects::AccountId;
+use abstract_std::{proxy, PROXY};
+use cosmwasm_std::{wasm_execute, Addr, BankMsg, Coin, CosmosMsg};
+use cw20::Cw20ExecuteMsg;
+use cw_asset::AssetInfo;
+use cw_orch::contract::Deploy;
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::BankQuerier;
+use cw_orch::prelude::*;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use log::warn;
+
+use crate::amount::query_balance;
+use crate::error::{IcaaError, IcaaResult};
+use crate::wait::WaitStrategy;
+use crate::{list_remote_proxies, IBC_CLIENT_ID};
+use crate::{load_account, run_log, sweep};
+
+/// Name given to sub-accounts that were swept and can be recycled by [`reuse_or_create`].
+pub const DECOMMISSIONED_NAME: &str = "decommissioned";
+
+const PAGE_LIMIT: u8 = 10;
+
+#[derive(Debug, Clone)]
+pub struct SubAccount {
+    pub id: AccountId,
+    pub name: String,
+    pub balances: Vec<Coin>,
+    pub remote_proxies: Vec<(ChainName, Option<String>)>,
+}
+
+impl SubAccount {
+    pub fn is_decommissioned(&self) -> bool {
+        self.name == DECOMMISSIONED_NAME
+    }
+}
+
+/// Ids of every sub-account of `parent`.
+pub fn sub_account_ids(parent: &AbstractAccount<Daemon>) -> IcaaResult<Vec<u32>> {
+    let mut ids = vec![];
+    loop {
+        let page: SubAccountIdsResponse =
+            parent.manager.query(&manager::QueryMsg::SubAccountIds {
+                start_after: ids.last().copied(),
+                limit: Some(PAGE_LIMIT),
+            })?;
+        let done = page.sub_accounts.len() < PAGE_LIMIT as usize;
+        ids.extend(page.sub_accounts);
+        if done {
+            return Ok(ids);
+        }
+    }
+}
+
+/// Every sub-account of `parent` with its name, home balances and remote proxies.
+pub fn list_sub_accounts(
+    chain: &Daemon,
+    parent: &AbstractAccount<Daemon>,
+) -> IcaaResult<Vec<SubAccount>> {
+    let abstr = Abstract::load_from(chain.clone())?;
+    sub_account_ids(parent)?
+        .into_iter()
+        .map(|seq| {
+            let account = AbstractAccount::new(&abstr, AccountId::local(seq));
+            let info: InfoResponse = account.manager.query(&manager::QueryMsg::Info {})?;
+            let balances = chain
+                .bank_querier()
+                .balance(account.proxy.address()?, None)?;
+            let remote_proxies = if account.manager.is_module_installed(IBC_CLIENT_ID)? {
+                list_remote_proxies(chain, &account)?
+            } else {
+                vec![]
+            };
+            Ok(SubAccount {
+                id: account.id()?,
+                name: info.info.name,
+                balances,
+                remote_proxies,
+            })
+        })
+        .collect()
+}
+
+/// The sub-account `seq` of `parent`, failing with [`IcaaError::NotSubAccount`] for any other
+/// account.
+pub fn load_sub_account(
+    chain: &Daemon,
+    parent: &AbstractAccount<Daemon>,
+    seq: u32,
+) -> IcaaResult<AbstractAccount<Daemon>> {
+    if !sub_account_ids(parent)?.contains(&seq) {
+        return Err(IcaaError::NotSubAccount {
+            account: AccountId::local(seq),
+            parent: parent.id()?,
+        });
+    }
+    load_account(chain, seq)
+}
+
+/// Bring the funds of every remote account of the sub-account `seq` of `parent` home, nested ones
+/// included, then send its native balances and the `cw20s` it holds to the parent.
+///
+/// `chains` maps the chains we're connected to by chain name, see [`sweep::chains_by_name`].
+/// Returns what was moved to the parent, cw20s with their address as denom.
+pub fn sweep(
+    interchain: &DaemonInterchainEnv,
+    chain: &Daemon,
+    parent: &AbstractAccount<Daemon>,
+    seq: u32,
+    chains: &HashMap<String, Daemon>,
+    cw20s: &[String],
+    strategy: &WaitStrategy,
+) -> IcaaResult<Vec<Coin>> {
+    let sub_account = load_sub_account(chain, parent, seq)?;
+    for holding in sweep::sweep(interchain, chain, &sub_account, chains, strategy)? {
+        if holding.balances.is_none() {
+            warn!(
+                "Not connected to {}, its funds may not have come home",
+                holding.path_string()
+            );
+        }
+    }
+
+    let proxy = sub_account.proxy.addr_str()?;
+    let parent_proxy = parent.proxy.addr_str()?;
+    let mut swept = chain.bank_querier().balance(&proxy, None)?;
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !swept.is_empty() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: parent_proxy.clone(),
+                amount: swept.clone(),
+            }
+            .into(),
+        );
+    }
+    for token in cw20s {
+        let amount = query_balance(chain, &AssetInfo::Cw20(Addr::unchecked(token)), &proxy)?;
+        if amount.is_zero() {
+            continue;
+        }
+        let transfer = Cw20ExecuteMsg::Transfer {
+            recipient: parent_proxy.clone(),
+            amount,
+        };
+        msgs.push(wasm_execute(token, &transfer, vec![])?.into());
+        swept.push(Coin::new(amount.u128(), token));
+    }
+    if msgs.is_empty() {
+        warn!("Sub-account {} holds nothing", sub_account.id()?);
+        return Ok(swept);
+    }
+    warn!(
+        "Sweeping {:?} from sub-account {} to {}",
+        swept,
+        sub_account.id()?,
+        parent.id()?
+    );
+    let tx = sub_account
+        .manager
+        .execute_on_module(PROXY, proxy::ExecuteMsg::ModuleAction { msgs })?;
+    run_log::tx(
+        "sweep sub-account",
+        chain,
+        Some(sub_account.id()?.to_string()),
+        &tx,
+    );
+    Ok(swept)
+}
+
+/// Sweep the sub-account `seq` of `parent` and mark it as recyclable.
+pub fn decommission(
+    interchain: &DaemonInterchainEnv,
+    chain: &Daemon,
+    parent: &AbstractAccount<Daemon>,
+    seq: u32,
+    chains: &HashMap<String, Daemon>,
+    cw20s: &[String],
+    strategy: &WaitStrategy,
+) -> IcaaResult<()> {
+    sweep(interchain, chain, parent, seq, chains, cw20s, strategy)?;
+    rename(&load_account(chain, seq)?, DECOMMISSIONED_NAME)?;
+    warn!("Decommissioned sub-account {}", AccountId::local(seq));
+    Ok(())
+}
+
+/// A sub-account of `parent` named `name`: an existing one, a recycled decommissioned one or a new one.
+pub fn reuse_or_create(
+    chain: &Daemon,
+    parent: &AbstractAccount<Daemon>,
+    name: &str,
+) -> IcaaResult<AbstractAccount<Daemon>> {
+    let abstr = Abstract::load_from(chain.clone())?;
+    let sub_accounts = list_sub_accounts(chain, parent)?;
+
+    if let Some(existing) = sub_accounts.iter().find(|sub| sub.name == name) {
+        warn!("Reusing sub-account {} ({})", existing.id, name);
+        return Ok(AbstractAccount::new(&abstr, existing.id.clone()));
+    }
+    if let Some(recycled) = sub_accounts.iter().find(|sub| sub.is_decommissioned()) {
+        warn!(
+            "Recycling decommissioned sub-account {} as {}",
+            recycled.id, name
+        );
+        let account = AbstractAccount::new(&abstr, recycled.id.clone());
+        rename(&account, name)?;
+        return Ok(account);
+    }
+
+    warn!("Creating sub-account {} under {}", name, parent.id()?);
+    let client = AbstractClient::new(chain.clone()).map_err(anyhow::Error::from)?;
+    let parent_account = client
+        .account_from(AccountSource::AccountId(parent.id()?))
+        .map_err(anyhow::Error::from)?;
+    let sub_account = client
+        .account_builder()
+        .name(name)
+        .sub_account(&parent_account)
+        .build()
+        .map_err(anyhow::Error::from)?;
+    Ok(AbstractAccount::new(
+        &abstr,
+        sub_account.id().map_err(anyhow::Error::from)?,
+    ))
+}
+
+fn rename(account: &AbstractAccount<Daemon>, name: &str) -> IcaaResult<()> {
+    account.manager.execute(
+        &manager::ExecuteMsg::UpdateInfo {
+            name: Some(name.to_string()),
+            description: None,
+            link: None,
+        },
+        None,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+
+use abstract_interface::{Abstract, AbstractAccount, ManagerExecFns};
+use abstract_std::ibc_host::{HelperAction, HostAction};
+use abstract_std::objects::account::AccountTrace;
+use abstract_std::objects::chain_name::ChainName;
+use abstract_std::objects::AccountId;
+use abstract_std::{ibc_client, proxy, PROXY};
+use cosmwasm_std::Coin;
+use cw_orch::contract::Deploy;
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::BankQuerier;
+use cw_orch::prelude::*;
+use cw_orch_interchain::prelude::{DaemonInterchainEnv, InterchainEnv};
+use log::warn;
+
+use crate::error::IcaaResult;
+use crate::remote::exec_on_proxy;
+use crate::timeout;
+use crate::wait::WaitStrategy;
+use crate::{chain_name, list_remote_proxies, IBC_CLIENT_ID};
+
+/// How deep we follow remote accounts of remote accounts.
+pub const MAX_DEPTH: usize = 4;
+
+/// Funds held by a (possibly nested) remote account of a home account.
+#[derive(Debug, Clone)]
+pub struct RemoteHolding {
+    pub home_account: AccountId,
+    /// Chains hopped through from the home chain, the last one holds the account.
+    pub path: Vec<ChainName>,
+    pub proxy: Option<String>,
+    /// `None` when we are not connected to the chain holding the account.
+    pub balances: Option<Vec<Coin>>,
+}
+
+impl RemoteHolding {
+    pub fn is_empty(&self) -> bool {
+        matches!(&self.balances, Some(balances) if balances.is_empty())
+    }
+
+    pub fn path_string(&self) -> String {
+        self.path
+            .iter()
+            .map(ChainName::to_string)
+            .collect::<Vec<_>>()
+            .join(">")
+    }
+}
+
+/// Every remote account reachable from `account` with its balances, nested accounts included.
+///
+/// `chains` maps the chains we're connected to by chain name; accounts on other chains are listed
+/// without balances.
+pub fn scan(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    chains: &HashMap<String, Daemon>,
+) -> IcaaResult<Vec<RemoteHolding>> {
+    let mut holdings = vec![];
+    if account.manager.is_module_installed(IBC_CLIENT_ID)? {
+        let home_account = account.id()?;
+        scan_level(
+            &chain_name(home),
+            &home_account,
+            &[],
+            list_remote_proxies(home, account)?,
+            chains,
+            &mut holdings,
+        )?;
+    }
+    Ok(holdings)
+}
+
+fn scan_level(
+    home_chain: &ChainName,
+    home_account: &AccountId,
+    parent_path: &[ChainName],
+    remote_proxies: Vec<(ChainName, Option<String>)>,
+    chains: &HashMap<String, Daemon>,
+    holdings: &mut Vec<RemoteHolding>,
+) -> IcaaResult<()> {
+    for (chain, proxy) in remote_proxies {
+        let mut path = parent_path.to_vec();
+        path.push(chain.clone());
+
+        let Some(remote) = chains.get(&chain.to_string()) else {
+            holdings.push(RemoteHolding {
+                home_account: home_account.clone(),
+                path,
+                proxy,
+                balances: None,
+            });
+            continue;
+        };
+        let balances = match &proxy {
+            Some(proxy) => remote.bank_querier().balance(proxy.clone(), None)?,
+            None => vec![],
+        };
+        holdings.push(RemoteHolding {
+            home_account: home_account.clone(),
+            path: path.clone(),
+            proxy,
+            balances: Some(balances),
+        });
+
+        if path.len() >= MAX_DEPTH {
+            continue;
+        }
+        let remote_account = AbstractAccount::new(
+            &Abstract::load_from(remote.clone())?,
+            remote_account_id(home_chain, home_account, &path)?,
+        );
+        if remote_account.manager.is_module_installed(IBC_CLIENT_ID)? {
+            let nested = list_remote_proxies(remote, &remote_account)?;
+            scan_level(home_chain, home_account, &path, nested, chains, holdings)?;
+        }
+    }
+    Ok(())
+}
+
+/// The id, on the last chain of `path`, of the remote account of `home_account` (living on `home_chain`).
+pub fn remote_account_id(
+    home_chain: &ChainName,
+    home_account: &AccountId,
+    path: &[ChainName],
+) -> IcaaResult<AccountId> {
+    let mut trace = match home_account.trace() {
+        AccountTrace::Local => vec![],
+        AccountTrace::Remote(trace) => trace.clone(),
+    };
+    trace.push(home_chain.clone());
+    trace.extend(path[..path.len() - 1].iter().cloned());
+    Ok(AccountId::new(
+        home_account.seq(),
+        AccountTrace::Remote(trace),
+    )?)
+}
+
+/// The proxy message, executed by the home account, that runs `action` at the end of `path`.
+pub fn nested_remote_action(
+    path: &[ChainName],
+    action: HostAction,
+) -> IcaaResult<proxy::ExecuteMsg> {
+    let (first, rest) = path
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("empty remote path"))?;
+    let action = if rest.is_empty() {
+        action
+    } else {
+        HostAction::Dispatch {
+            manager_msgs: vec![exec_on_proxy(&nested_remote_action(rest, action)?)?],
+        }
+    };
+    Ok(proxy::ExecuteMsg::IbcAction {
+        msg: ibc_client::ExecuteMsg::RemoteAction {
+            host_chain: first.to_string(),
+            action,
+        },
+    })
+}
+
+/// Bring the funds of every remote account of `account` home, deepest accounts first.
+///
+/// Each `SendAllBack` moves funds one hop closer to home, so after sweeping a level the next
+/// level up is scanned again and swept until everything reached the home account. Levels wait for
+/// each other, so the waits are never detached.
+pub fn sweep(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    chains: &HashMap<String, Daemon>,
+    strategy: &WaitStrategy,
+) -> IcaaResult<Vec<RemoteHolding>> {
+    let strategy = strategy.clone().detach(false);
+    let mut swept = vec![];
+    let max_depth = scan(home, account, chains)?
+        .iter()
+        .map(|holding| holding.path.len())
+        .max()
+        .unwrap_or_default();
+
+    for depth in (1..=max_depth).rev() {
+        let level: Vec<RemoteHolding> = scan(home, account, chains)?
+            .into_iter()
+            .filter(|holding| holding.path.len() == depth && !holding.is_empty())
+            .collect();
+        for holding in level {
+            warn!(
+                "Sweeping {} from {} for {}",
+                holding
+                    .balances
+                    .as_ref()
+                    .map(|b| format!("{:?}", b))
+                    .unwrap_or_else(|| "unknown balances".into()),
+                holding.path_string(),
+                holding.home_account
+            );
+            let tx = account.manager.execute_on_module(
+                PROXY,
+                nested_remote_action(
+                    &holding.path,
+                    HostAction::Helpers(HelperAction::SendAllBack),
+                )?,
+            )?;
+            timeout::wait_checked(
+                interchain,
+                home,
+                &holding.home_account,
+                &format!("sweep {}", holding.path_string()),
+                tx,
+                &strategy,
+            )?;
+            swept.push(holding);
+        }
+    }
+    Ok(swept)
+}
+
+/// Map the chains of `interchain` by Abstract chain name, for [`scan`] and [`sweep`].
+pub fn chains_by_name(
+    interchain: &DaemonInterchainEnv,
+    chain_ids: &[&str],
+) -> IcaaResult<HashMap<String, Daemon>> {
+    chain_ids
+        .iter()
+        .map(|id| {
+            let chain = interchain.chain(*id).map_err(anyhow::Error::from)?;
+            Ok((chain_name(&chain).to_string(), chain))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use abstract_std::manager;
+    use cosmwasm_std::from_json;
+
+    use super::*;
+
+    fn chains(chain_ids: &[&str]) -> Vec<ChainName> {
+        chain_ids
+            .iter()
+            .map(|chain_id| ChainName::from_chain_id(chain_id))
+            .collect()
+    }
+
+    /// The host chains a nested remote action hops through, and the action run at the end.
+    fn hops(msg: proxy::ExecuteMsg) -> (Vec<String>, HostAction) {
+        let proxy::ExecuteMsg::IbcAction {
+            msg: ibc_client::ExecuteMsg::RemoteAction { host_chain, action },
+        } = msg
+        else {
+            panic!("not a remote action");
+        };
+        let HostAction::Dispatch { manager_msgs } = &action else {
+            return (vec![host_chain], action);
+        };
+        let [manager::ExecuteMsg::ExecOnModule {
+            module_id,
+            exec_msg,
+        }] = manager_msgs.as_slice()
+        else {
+            return (vec![host_chain], action);
+        };
+        assert_eq!(module_id, PROXY);
+        let (mut rest, action) = hops(from_json(exec_msg).unwrap());
+        rest.insert(0, host_chain);
+        (rest, action)
+    }
+
+    #[test]
+    fn nested_action_hops_through_every_chain() {
+        for path in [
+            chains(&["osmosis-1", "neutron-1"]),
+            chains(&["osmosis-1", "neutron-1", "archway-1"]),
+        ] {
+            let msg = nested_remote_action(&path, HostAction::Helpers(HelperAction::SendAllBack))
+                .unwrap();
+            let (host_chains, action) = hops(msg);
+            let expected: Vec<String> = path.iter().map(ChainName::to_string).collect();
+            assert_eq!(host_chains, expected);
+            assert!(matches!(
+                action,
+                HostAction::Helpers(HelperAction::SendAllBack)
+            ));
+        }
+    }
+
+    #[test]
+    fn nested_action_needs_a_path() {
+        assert!(nested_remote_action(&[], HostAction::Helpers(HelperAction::SendAllBack)).is_err());
+    }
+
+    #[test]
+    fn remote_account_id_traces_every_hop_but_the_last() {
+        let juno = ChainName::from_chain_id("juno-1");
+        let home_account = AccountId::local(7);
+
+        let two_hops =
+            remote_account_id(&juno, &home_account, &chains(&["osmosis-1", "neutron-1"])).unwrap();
+        assert_eq!(two_hops.seq(), 7);
+        assert_eq!(
+            two_hops.trace(),
+            &AccountTrace::Remote(chains(&["juno-1", "osmosis-1"]))
+        );
+
+        let three_hops = remote_account_id(
+            &juno,
+            &home_account,
+            &chains(&["osmosis-1", "neutron-1", "archway-1"]),
+        )
+        .unwrap();
+        assert_eq!(
+            three_hops.trace(),
+            &AccountTrace::Remote(chains(&["juno-1", "osmosis-1", "neutron-1"]))
+        );
+    }
+
+    #[test]
+    fn remote_account_id_of_a_remote_home_account_extends_its_trace() {
+        let home_account = AccountId::new(3, AccountTrace::Remote(chains(&["archway-1"]))).unwrap();
+        let id = remote_account_id(
+            &ChainName::from_chain_id("juno-1"),
+            &home_account,
+            &chains(&["osmosis-1", "neutron-1"]),
+        )
+        .unwrap();
+        assert_eq!(
+            id.trace(),
+            &AccountTrace::Remote(chains(&["archway-1", "juno-1", "osmosis-1"]))
+        );
+    }
+}
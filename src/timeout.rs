@@ -0,0 +1,316 @@
+use std::fmt;
+
+use abstract_std::objects::AccountId;
+use cosmwasm_std::Coin;
+use cw_orch::daemon::{CosmTxResponse, Daemon};
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use cw_orch_interchain::types::{IbcPacketOutcome, NestedPacketsFlow};
+use log::warn;
+
+use crate::costs::parse_coins;
+use crate::error::{IcaaError, IcaaResult};
+use crate::packets::{packet_results, PacketResult};
+use crate::run_log;
+use crate::wait::{self, WaitStrategy};
+
+/// What a timed out packet left behind.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeoutEffect {
+    /// An ICS-20 transfer timed out and its funds were refunded on the sending chain.
+    Refunded { receiver: String, funds: Vec<Coin> },
+    /// A remote action timed out, nothing was executed on the host.
+    Dropped,
+}
+
+/// A packet of a flow that timed out.
+#[derive(Debug, Clone)]
+pub struct PacketTimeout {
+    /// Chain that sent the packet, where the timeout was processed.
+    pub src_chain: String,
+    /// Number of IBC hops from the home transaction, 1 for packets sent by it.
+    pub depth: usize,
+    pub timeout_tx: String,
+    pub effect: TimeoutEffect,
+}
+
+impl fmt::Display for PacketTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "packet from {} (hop {}) timed out in {}: ",
+            self.src_chain, self.depth, self.timeout_tx
+        )?;
+        match &self.effect {
+            TimeoutEffect::Refunded { receiver, funds } => {
+                write!(f, "{:?} refunded to {}", funds, receiver)
+            }
+            TimeoutEffect::Dropped => write!(f, "remote action dropped"),
+        }
+    }
+}
+
+/// Every packet of `flow` that timed out, nested hops included.
+pub fn timeouts(flow: &NestedPacketsFlow<Daemon>) -> Vec<PacketTimeout> {
+    let mut timeouts = vec![];
+    collect_timeouts(flow, 1, &mut timeouts);
+    timeouts
+}
+
+fn collect_timeouts(
+    flow: &NestedPacketsFlow<Daemon>,
+    depth: usize,
+    timeouts: &mut Vec<PacketTimeout>,
+) {
+    for packet in &flow.packets {
+        match &packet.outcome {
+            IbcPacketOutcome::Timeout { timeout_tx } => timeouts.push(PacketTimeout {
+                src_chain: timeout_tx.tx_id.chain_id.clone(),
+                depth,
+                timeout_tx: timeout_tx.tx_id.response.txhash.clone(),
+                effect: timeout_effect(&timeout_tx.tx_id.response),
+            }),
+            IbcPacketOutcome::Success { receive_tx, .. } => {
+                collect_timeouts(receive_tx, depth + 1, timeouts)
+            }
+        }
+    }
+}
+
+/// ICS-20 refunds emit a `timeout` event with the refunded funds, anything else is a dropped action.
+fn timeout_effect(resp: &CosmTxResponse) -> TimeoutEffect {
+    let mut receiver = String::new();
+    let mut funds = vec![];
+    for event in resp.get_events("timeout") {
+        let (Some(refund_receiver), Some(denom), Some(amount)) = (
+            event.get_first_attribute_value("refund_receiver"),
+            event.get_first_attribute_value("refund_denom"),
+            event.get_first_attribute_value("refund_amount"),
+        ) else {
+            continue;
+        };
+        receiver = refund_receiver;
+        funds.extend(parse_coins(&format!("{amount}{denom}")));
+    }
+    if funds.is_empty() {
+        TimeoutEffect::Dropped
+    } else {
+        TimeoutEffect::Refunded { receiver, funds }
+    }
+}
+
+/// A step whose packets timed out, with the account it acted for and where the funds ended up.
+#[derive(Debug, Clone)]
+pub struct TimeoutError {
+    pub step: String,
+    pub account: AccountId,
+    /// Number of packets sent by the home transaction.
+    pub sent: usize,
+    pub timeouts: Vec<PacketTimeout>,
+}
+
+impl TimeoutError {
+    /// Retrying is only safe when nothing of the flow went through, i.e. every packet sent by the
+    /// home transaction timed out. When some of them were received the result is partial.
+    pub fn is_retryable(&self) -> bool {
+        self.timeouts.len() == self.sent && self.timeouts.iter().all(|timeout| timeout.depth == 1)
+    }
+}
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.timeouts.is_empty() {
+            return write!(f, "{} for account {} timed out", self.step, self.account);
+        }
+        write!(
+            f,
+            "{} for account {} timed out: {}",
+            self.step,
+            self.account,
+            self.timeouts
+                .iter()
+                .map(PacketTimeout::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        )
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// Fail with a [`TimeoutError`] if any packet of `flow` timed out.
+pub fn check(
+    account: &AccountId,
+    step: &str,
+    flow: &NestedPacketsFlow<Daemon>,
+) -> Result<(), TimeoutError> {
+    let timeouts = timeouts(flow);
+    if timeouts.is_empty() {
+        return Ok(());
+    }
+    Err(TimeoutError {
+        step: step.to_string(),
+        account: account.clone(),
+        sent: flow.packets.len(),
+        timeouts,
+    })
+}
+
+/// Fail with [`IcaaError::ErrorAck`] on the first packet of `flow` acked with an error.
+pub fn check_acks(
+    account: &AccountId,
+    step: &str,
+    flow: &NestedPacketsFlow<Daemon>,
+) -> IcaaResult<()> {
+    for packet in packet_results(flow) {
+        if let PacketResult::ErrorAck { dest_chain, error } = packet {
+            return Err(IcaaError::ErrorAck {
+                step: step.to_string(),
+                account: account.clone(),
+                dest_chain,
+                error,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Wait for the packets of `tx` and fail if any of them timed out or was acked with an error.
+///
+/// A detached or expired wait fails too, pointing at `icaa wait` to resume it.
+pub fn wait_checked(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AccountId,
+    step: &str,
+    tx: CosmTxResponse,
+    strategy: &WaitStrategy,
+) -> IcaaResult<NestedPacketsFlow<Daemon>> {
+    run_log::tx(step, home, Some(account.to_string()), &tx);
+    let flow = wait::wait(interchain, home, step, tx, strategy)?.done()?;
+    check(account, step, &flow)?;
+    check_acks(account, step, &flow)?;
+    Ok(flow)
+}
+
+/// Submit an IBC action with `submit`, submitting it again when its first hop timed out.
+///
+/// The packet timeout is set by the IBC client when the packet is sent, so a resubmitted action
+/// gets a fresh timeout window. Only actions of which every packet timed out on the first hop are
+/// retried, otherwise some of it already had its effect and the partial result is returned as a
+/// [`TimeoutError`]. Retries need the outcome, so the wait is never detached.
+pub fn submit_with_retry(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AccountId,
+    step: &str,
+    retries: u32,
+    strategy: &WaitStrategy,
+    submit: impl Fn() -> IcaaResult<CosmTxResponse>,
+) -> IcaaResult<NestedPacketsFlow<Daemon>> {
+    let strategy = strategy.clone().detach(false);
+    retry(retries, || {
+        wait_checked(interchain, home, account, step, submit()?, &strategy)
+    })
+}
+
+/// Run `attempt` again, up to `retries` times, while it fails with a retryable timeout.
+fn retry<T>(retries: u32, mut attempt: impl FnMut() -> IcaaResult<T>) -> IcaaResult<T> {
+    let mut attempts = 0;
+    loop {
+        match attempt() {
+            Err(IcaaError::Timeout(err)) if err.is_retryable() && attempts < retries => {
+                attempts += 1;
+                warn!("{}, retrying ({}/{})", err, attempts, retries);
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeout(depth: usize) -> PacketTimeout {
+        PacketTimeout {
+            src_chain: "juno-1".to_string(),
+            depth,
+            timeout_tx: "ABCD".to_string(),
+            effect: TimeoutEffect::Dropped,
+        }
+    }
+
+    fn timed_out(sent: usize, depths: &[usize]) -> TimeoutError {
+        TimeoutError {
+            step: "send funds".to_string(),
+            account: AccountId::local(1),
+            sent,
+            timeouts: depths.iter().copied().map(timeout).collect(),
+        }
+    }
+
+    #[test]
+    fn retryable_when_every_first_hop_timed_out() {
+        assert!(timed_out(1, &[1]).is_retryable());
+        assert!(timed_out(2, &[1, 1]).is_retryable());
+    }
+
+    #[test]
+    fn not_retryable_when_some_packets_went_through() {
+        // One of the two packets sent was received.
+        assert!(!timed_out(2, &[1]).is_retryable());
+        // A later hop timed out, the first one had its effect.
+        assert!(!timed_out(1, &[2]).is_retryable());
+        assert!(!timed_out(2, &[1, 2]).is_retryable());
+    }
+
+    #[test]
+    fn retries_first_hop_timeouts() {
+        let mut attempts = 0;
+        let result = retry(2, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(timed_out(1, &[1]).into())
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_the_retries() {
+        let mut attempts = 0;
+        let result: IcaaResult<()> = retry(2, || {
+            attempts += 1;
+            Err(timed_out(1, &[1]).into())
+        });
+        assert!(matches!(result, Err(IcaaError::Timeout(_))));
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn surfaces_partial_results() {
+        let mut attempts = 0;
+        let result: IcaaResult<()> = retry(2, || {
+            attempts += 1;
+            Err(timed_out(2, &[1]).into())
+        });
+        match result {
+            Err(IcaaError::Timeout(err)) => assert_eq!(err.sent, 2),
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let mut attempts = 0;
+        let result: IcaaResult<()> = retry(2, || {
+            attempts += 1;
+            Err(IcaaError::InvalidAmount("0".to_string()))
+        });
+        assert!(matches!(result, Err(IcaaError::InvalidAmount(_))));
+        assert_eq!(attempts, 1);
+    }
+}
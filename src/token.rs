@@ -0,0 +1,266 @@
+use abstract_interface::AbstractAccount;
+use cosmwasm_std::{to_json_binary, wasm_execute, Addr, Binary, CosmosMsg, Uint128};
+use cw20::Cw20ExecuteMsg;
+use cw_asset::AssetInfo;
+use cw_orch::daemon::Daemon;
+use cw_orch::environment::WasmQuerier;
+use cw_orch_interchain::prelude::DaemonInterchainEnv;
+use cw_orch_interchain::types::NestedPacketsFlow;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::amount::{decimals, query_balance, to_base_units, AmountSpec};
+use crate::error::{IcaaError, IcaaResult};
+use crate::remote::{execute_remote, remote_proxy};
+use crate::wait::WaitStrategy;
+use crate::{chain_name, guard};
+
+/// A CW20 operation executed by a remote account, amounts are evaluated against its balance.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cw20Action {
+    Transfer {
+        recipient: String,
+        amount: AmountSpec,
+    },
+    /// Send to `contract`, which receives `msg` in a `Receive` hook.
+    Send {
+        contract: String,
+        amount: AmountSpec,
+        msg: Binary,
+    },
+    IncreaseAllowance {
+        spender: String,
+        amount: AmountSpec,
+    },
+    /// Bridge over ICS-20 through the cw20-ics20 contract `ics20` of the remote chain.
+    Bridge {
+        ics20: String,
+        channel: String,
+        remote_address: String,
+        amount: AmountSpec,
+        /// Packet timeout in seconds, the contract's default when `None`.
+        timeout: Option<u64>,
+    },
+}
+
+impl Cw20Action {
+    fn amount(&self) -> &AmountSpec {
+        match self {
+            Cw20Action::Transfer { amount, .. }
+            | Cw20Action::Send { amount, .. }
+            | Cw20Action::IncreaseAllowance { amount, .. }
+            | Cw20Action::Bridge { amount, .. } => amount,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Cw20Action::Transfer { recipient, .. } => format!("transfer to {recipient}"),
+            Cw20Action::Send { contract, .. } => format!("send to {contract}"),
+            Cw20Action::IncreaseAllowance { spender, .. } => format!("allowance for {spender}"),
+            Cw20Action::Bridge {
+                channel,
+                remote_address,
+                ..
+            } => format!("bridge to {remote_address} over {channel}"),
+        }
+    }
+
+    fn to_msg(&self, token: &str, amount: Uint128) -> IcaaResult<CosmosMsg> {
+        let msg = match self.clone() {
+            Cw20Action::Transfer { recipient, .. } => {
+                Cw20ExecuteMsg::Transfer { recipient, amount }
+            }
+            Cw20Action::Send { contract, msg, .. } => Cw20ExecuteMsg::Send {
+                contract,
+                amount,
+                msg,
+            },
+            Cw20Action::IncreaseAllowance { spender, .. } => Cw20ExecuteMsg::IncreaseAllowance {
+                spender,
+                amount,
+                expires: None,
+            },
+            Cw20Action::Bridge {
+                ics20,
+                channel,
+                remote_address,
+                timeout,
+                ..
+            } => Cw20ExecuteMsg::Send {
+                contract: ics20,
+                amount,
+                msg: to_json_binary(&Ics20TransferMsg {
+                    channel,
+                    remote_address,
+                    timeout,
+                })?,
+            },
+        };
+        Ok(wasm_execute(token, &msg, vec![])?.into())
+    }
+}
+
+/// The `Send` hook msg of cw20-ics20.
+#[derive(Debug, Clone, Serialize)]
+struct Ics20TransferMsg {
+    channel: String,
+    remote_address: String,
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AllowedResponse {
+    is_allowed: bool,
+}
+
+/// The part of the cw20-ics20 config deciding which tokens are accepted.
+#[derive(Debug, Clone, Deserialize)]
+struct Ics20Config {
+    /// When set, tokens missing from the allowlist are accepted with this gas limit.
+    default_gas_limit: Option<u64>,
+}
+
+/// Fail unless `ics20` is a cw20-ics20 contract with `channel` open that accepts `token`, either
+/// allowlisted or through the contract's default gas limit.
+pub fn check_bridge(remote: &Daemon, ics20: &str, channel: &str, token: &str) -> IcaaResult<()> {
+    let channel_info: Result<serde_json::Value, _> = remote
+        .wasm_querier()
+        .smart_query(ics20, &json!({ "channel": { "id": channel } }));
+    if let Err(e) = channel_info {
+        return Err(anyhow::anyhow!(
+            "{} on {} is not a cw20-ics20 contract with channel {}: {}",
+            ics20,
+            chain_name(remote),
+            channel,
+            e
+        )
+        .into());
+    }
+    let allowed: AllowedResponse = remote
+        .wasm_querier()
+        .smart_query(ics20, &json!({ "allowed": { "contract": token } }))?;
+    if !allowed.is_allowed {
+        let config: Ics20Config = remote
+            .wasm_querier()
+            .smart_query(ics20, &json!({ "config": {} }))?;
+        if config.default_gas_limit.is_some() {
+            return Ok(());
+        }
+        return Err(IcaaError::UnsupportedAsset(format!(
+            "{token} is not allowed by the cw20-ics20 contract {ics20}"
+        )));
+    }
+    Ok(())
+}
+
+/// The allowance granted by `spec` in base units.
+///
+/// An allowance moves nothing, so it may exceed or precede the balance and isn't a spend for the
+/// guard. Only exact amounts make sense without a balance to take them from.
+fn allowance(spec: &AmountSpec, decimals: impl FnOnce() -> IcaaResult<u32>) -> IcaaResult<Uint128> {
+    match spec {
+        AmountSpec::Exact(amount) => to_base_units(*amount, decimals()?),
+        _ => Err(IcaaError::InvalidAmount(
+            "allowances take an exact amount, not `all`, `all-but` or a percentage".to_string(),
+        )),
+    }
+}
+
+/// Balance of the CW20 `token` held by `account`'s remote proxy on `remote`.
+pub fn remote_balance(
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    remote: &Daemon,
+    token: &str,
+) -> IcaaResult<Uint128> {
+    let proxy = remote_proxy(home, account, &chain_name(remote).to_string())?;
+    query_balance(remote, &AssetInfo::Cw20(Addr::unchecked(token)), proxy)
+}
+
+/// Execute `action` on the CW20 `token` on `remote` from `account`'s remote account.
+///
+/// The amount is evaluated against the remote proxy's balance before sending the packet, except
+/// for allowances, see [`allowance`].
+pub fn execute(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    remote: &Daemon,
+    token: &str,
+    action: &Cw20Action,
+    strategy: &WaitStrategy,
+) -> IcaaResult<NestedPacketsFlow<Daemon>> {
+    let host_chain = chain_name(remote).to_string();
+    if let Cw20Action::Bridge { ics20, channel, .. } = action {
+        check_bridge(remote, ics20, channel, token)?;
+    }
+    let info = AssetInfo::Cw20(Addr::unchecked(token));
+    let proxy = remote_proxy(home, account, &host_chain)?;
+    let amount = match action {
+        Cw20Action::IncreaseAllowance { amount, .. } => {
+            allowance(amount, || decimals(remote, &info))?
+        }
+        _ => {
+            let balance = query_balance(remote, &info, &proxy)?;
+            if balance.is_zero() {
+                return Err(IcaaError::EmptyBalance {
+                    holder: format!("remote account {proxy}"),
+                    asset: token.to_string(),
+                });
+            }
+            let amount = action
+                .amount()
+                .evaluate(balance, || decimals(remote, &info))?;
+            guard::spend(remote, token, amount, &action.describe())?;
+            amount
+        }
+    };
+
+    warn!(
+        "Executing {} {} on {}: {}",
+        amount,
+        token,
+        host_chain,
+        action.describe()
+    );
+    execute_remote(
+        interchain,
+        home,
+        account,
+        &host_chain,
+        &format!("cw20 {} on {host_chain}", action.describe()),
+        vec![action.to_msg(token, amount)?],
+        strategy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn allowance_may_exceed_the_balance() {
+        let spec = AmountSpec::from_str("10").unwrap();
+        let balance = Uint128::new(1_000);
+        assert!(spec.evaluate(balance, || Ok(6)).is_err());
+        assert_eq!(
+            allowance(&spec, || Ok(6)).unwrap(),
+            Uint128::new(10_000_000)
+        );
+    }
+
+    #[test]
+    fn allowance_needs_an_exact_amount() {
+        for spec in ["all", "all-but 1", "50%"] {
+            let spec = AmountSpec::from_str(spec).unwrap();
+            assert!(matches!(
+                allowance(&spec, || Ok(6)),
+                Err(IcaaError::InvalidAmount(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cosmrs::tendermint::Time;
+use cw_orch::daemon::{CosmTxResponse, Daemon};
+use cw_orch_interchain::types::{IbcPacketOutcome, NestedPacketsFlow};
+use log::info;
+
+/// Which part of a packet's lifecycle a transaction is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    /// The transaction we signed, included on the home chain.
+    Inclusion,
+    /// A packet received on its destination chain.
+    Receive,
+    /// The ack of a packet, back on its source chain.
+    Ack,
+    /// A packet timeout, back on its source chain.
+    Timeout,
+}
+
+impl fmt::Display for SpanKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpanKind::Inclusion => write!(f, "inclusion"),
+            SpanKind::Receive => write!(f, "receive"),
+            SpanKind::Ack => write!(f, "ack"),
+            SpanKind::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+/// A transaction of a flow with the block time it was included at.
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub kind: SpanKind,
+    pub chain_id: String,
+    pub txhash: String,
+    pub height: u64,
+    /// Block time in milliseconds since the epoch, `None` if the node didn't return it.
+    pub at_ms: Option<i128>,
+    /// Number of IBC hops from the home transaction.
+    pub depth: usize,
+}
+
+impl Span {
+    fn new(kind: SpanKind, chain_id: &str, resp: &CosmTxResponse, depth: usize) -> Self {
+        Self {
+            kind,
+            chain_id: chain_id.to_string(),
+            txhash: resp.txhash.clone(),
+            height: resp.height,
+            at_ms: Time::parse_from_rfc3339(&resp.timestamp)
+                .ok()
+                .map(|time| time.unix_timestamp_nanos() / 1_000_000),
+            depth,
+        }
+    }
+}
+
+/// Latency of one packet, from the transaction that sent it to its ack or timeout.
+#[derive(Debug, Clone)]
+pub struct HopLatency {
+    pub src_chain: String,
+    pub dest_chain: Option<String>,
+    pub depth: usize,
+    /// Relaying the packet: send tx included until receive tx included.
+    pub send_to_receive_ms: Option<i128>,
+    /// Relaying the ack: receive tx included until ack tx included.
+    pub receive_to_ack_ms: Option<i128>,
+    /// Send tx included until timeout tx included, for timed out packets.
+    pub send_to_timeout_ms: Option<i128>,
+}
+
+/// Timings of one step of a flow.
+#[derive(Debug, Clone)]
+pub struct StepTrace {
+    pub step: String,
+    /// Wall clock time the transaction was submitted at, if known.
+    pub submitted_ms: Option<i128>,
+    pub spans: Vec<Span>,
+    pub hops: Vec<HopLatency>,
+}
+
+impl StepTrace {
+    fn inclusion(&self) -> Option<&Span> {
+        self.spans
+            .iter()
+            .find(|span| span.kind == SpanKind::Inclusion)
+    }
+
+    /// Time spent by the home chain: submission until inclusion.
+    ///
+    /// Compares our wall clock with the block time, so it is only as accurate as the local clock.
+    pub fn submit_to_inclusion_ms(&self) -> Option<i128> {
+        Some(self.inclusion()?.at_ms? - self.submitted_ms?)
+    }
+
+    /// Inclusion of the home transaction until the last ack or timeout came back.
+    pub fn inclusion_to_done_ms(&self) -> Option<i128> {
+        let included = self.inclusion()?.at_ms?;
+        self.spans
+            .iter()
+            .filter(|span| matches!(span.kind, SpanKind::Ack | SpanKind::Timeout))
+            .filter_map(|span| span.at_ms)
+            .max()
+            .map(|done| done - included)
+    }
+}
+
+/// Records where the time of every step of a flow went.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyTrace {
+    pub steps: Vec<StepTrace>,
+}
+
+impl LatencyTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the home transaction of `flow` and every packet that followed it.
+    ///
+    /// Every span is logged on the `icaa::trace` target as it is recorded.
+    pub fn record_flow(
+        &mut self,
+        step: impl Into<String>,
+        submitted: Option<SystemTime>,
+        flow: &NestedPacketsFlow<Daemon>,
+    ) -> &StepTrace {
+        let mut trace = StepTrace {
+            step: step.into(),
+            submitted_ms: submitted
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_millis() as i128),
+            spans: vec![],
+            hops: vec![],
+        };
+        let inclusion = Span::new(
+            SpanKind::Inclusion,
+            &flow.tx_id.chain_id,
+            &flow.tx_id.response,
+            0,
+        );
+        trace.spans.push(inclusion.clone());
+        trace_packets(flow, &inclusion, &mut trace);
+
+        for span in &trace.spans {
+            info!(
+                target: "icaa::trace",
+                "step={:?} kind={} depth={} chain={} height={} at_ms={} tx={}",
+                trace.step,
+                span.kind,
+                span.depth,
+                span.chain_id,
+                span.height,
+                span.at_ms.map(|at| at.to_string()).unwrap_or_default(),
+                span.txhash
+            );
+        }
+        self.steps.push(trace);
+        self.steps.last().unwrap()
+    }
+}
+
+fn elapsed(from: &Span, to: &Span) -> Option<i128> {
+    Some(to.at_ms? - from.at_ms?)
+}
+
+fn trace_packets(flow: &NestedPacketsFlow<Daemon>, sent_by: &Span, trace: &mut StepTrace) {
+    let depth = sent_by.depth + 1;
+    for packet in &flow.packets {
+        match &packet.outcome {
+            IbcPacketOutcome::Timeout { timeout_tx } => {
+                let timeout = Span::new(
+                    SpanKind::Timeout,
+                    &timeout_tx.tx_id.chain_id,
+                    &timeout_tx.tx_id.response,
+                    depth,
+                );
+                trace.hops.push(HopLatency {
+                    src_chain: sent_by.chain_id.clone(),
+                    dest_chain: None,
+                    depth,
+                    send_to_receive_ms: None,
+                    receive_to_ack_ms: None,
+                    send_to_timeout_ms: elapsed(sent_by, &timeout),
+                });
+                trace.spans.push(timeout);
+            }
+            IbcPacketOutcome::Success {
+                receive_tx, ack_tx, ..
+            } => {
+                let receive = Span::new(
+                    SpanKind::Receive,
+                    &receive_tx.tx_id.chain_id,
+                    &receive_tx.tx_id.response,
+                    depth,
+                );
+                let ack = Span::new(
+                    SpanKind::Ack,
+                    &ack_tx.tx_id.chain_id,
+                    &ack_tx.tx_id.response,
+                    depth,
+                );
+                trace.hops.push(HopLatency {
+                    src_chain: sent_by.chain_id.clone(),
+                    dest_chain: Some(receive.chain_id.clone()),
+                    depth,
+                    send_to_receive_ms: elapsed(sent_by, &receive),
+                    receive_to_ack_ms: elapsed(&receive, &ack),
+                    send_to_timeout_ms: None,
+                });
+                trace.spans.push(receive.clone());
+                trace.spans.push(ack);
+                trace_packets(receive_tx, &receive, trace);
+            }
+        }
+    }
+}
+
+fn ms(value: Option<i128>) -> String {
+    value
+        .map(|ms| format!("{}ms", ms))
+        .unwrap_or_else(|| "?".into())
+}
+
+impl fmt::Display for StepTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}:", self.step)?;
+        if let Some(inclusion) = self.inclusion() {
+            writeln!(
+                f,
+                "  chain    {:<40} {:>10}",
+                format!("submit -> included on {}", inclusion.chain_id),
+                ms(self.submit_to_inclusion_ms())
+            )?;
+        }
+        for hop in &self.hops {
+            let indent = "  ".repeat(hop.depth - 1);
+            match &hop.dest_chain {
+                Some(dest_chain) => {
+                    writeln!(
+                        f,
+                        "  relayer  {:<40} {:>10}",
+                        format!("{}{} -> {} receive", indent, hop.src_chain, dest_chain),
+                        ms(hop.send_to_receive_ms)
+                    )?;
+                    writeln!(
+                        f,
+                        "  relayer  {:<40} {:>10}",
+                        format!("{}{} -> {} ack", indent, dest_chain, hop.src_chain),
+                        ms(hop.receive_to_ack_ms)
+                    )?;
+                }
+                None => writeln!(
+                    f,
+                    "  relayer  {:<40} {:>10}",
+                    format!("{}{} timeout", indent, hop.src_chain),
+                    ms(hop.send_to_timeout_ms)
+                )?,
+            }
+        }
+        writeln!(
+            f,
+            "  total    {:<40} {:>10}",
+            "included -> last ack",
+            ms(self.inclusion_to_done_ms())
+        )
+    }
+}
+
+impl fmt::Display for LatencyTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            write!(f, "{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(kind: SpanKind, chain_id: &str, at_ms: Option<i128>, depth: usize) -> Span {
+        Span {
+            kind,
+            chain_id: chain_id.to_string(),
+            txhash: format!("{kind}-{chain_id}"),
+            height: 1,
+            at_ms,
+            depth,
+        }
+    }
+
+    fn step(submitted_ms: Option<i128>, spans: Vec<Span>) -> StepTrace {
+        StepTrace {
+            step: "send funds".to_string(),
+            submitted_ms,
+            spans,
+            hops: vec![],
+        }
+    }
+
+    #[test]
+    fn block_time_of_a_tx() {
+        let resp = CosmTxResponse {
+            timestamp: "2024-01-01T00:00:01.500Z".to_string(),
+            ..Default::default()
+        };
+        let span = Span::new(SpanKind::Receive, "osmosis-1", &resp, 1);
+        assert_eq!(span.at_ms, Some(1_704_067_201_500));
+
+        let missing = Span::new(SpanKind::Receive, "osmosis-1", &Default::default(), 1);
+        assert_eq!(missing.at_ms, None);
+    }
+
+    #[test]
+    fn step_runs_until_its_last_ack_or_timeout() {
+        let trace = step(
+            Some(1_000),
+            vec![
+                span(SpanKind::Inclusion, "juno-1", Some(4_000), 0),
+                span(SpanKind::Receive, "osmosis-1", Some(10_000), 1),
+                span(SpanKind::Ack, "juno-1", Some(16_000), 1),
+                span(SpanKind::Receive, "neutron-1", Some(20_000), 2),
+                span(SpanKind::Timeout, "osmosis-1", Some(30_000), 2),
+            ],
+        );
+        assert_eq!(trace.submit_to_inclusion_ms(), Some(3_000));
+        assert_eq!(trace.inclusion_to_done_ms(), Some(26_000));
+    }
+
+    #[test]
+    fn missing_block_times_are_skipped_or_unknown() {
+        let trace = step(
+            None,
+            vec![
+                span(SpanKind::Inclusion, "juno-1", Some(4_000), 0),
+                span(SpanKind::Ack, "juno-1", Some(16_000), 1),
+                span(SpanKind::Ack, "juno-1", None, 1),
+            ],
+        );
+        assert_eq!(trace.submit_to_inclusion_ms(), None);
+        assert_eq!(trace.inclusion_to_done_ms(), Some(12_000));
+
+        let unknown_inclusion = step(
+            Some(1_000),
+            vec![
+                span(SpanKind::Inclusion, "juno-1", None, 0),
+                span(SpanKind::Ack, "juno-1", Some(16_000), 1),
+            ],
+        );
+        assert_eq!(unknown_inclusion.submit_to_inclusion_ms(), None);
+        assert_eq!(unknown_inclusion.inclusion_to_done_ms(), None);
+
+        let receive = span(SpanKind::Receive, "osmosis-1", None, 1);
+        assert_eq!(elapsed(&unknown_inclusion.spans[0], &receive), None);
+    }
+
+    #[test]
+    fn display_marks_unknown_latencies() {
+        let mut trace = step(
+            Some(1_000),
+            vec![span(SpanKind::Inclusion, "juno-1", Some(4_000), 0)],
+        );
+        trace.hops = vec![
+            HopLatency {
+                src_chain: "juno-1".to_string(),
+                dest_chain: Some("osmosis-1".to_string()),
+                depth: 1,
+                send_to_receive_ms: Some(6_000),
+                receive_to_ack_ms: None,
+                send_to_timeout_ms: None,
+            },
+            HopLatency {
+                src_chain: "osmosis-1".to_string(),
+                dest_chain: None,
+                depth: 2,
+                send_to_receive_ms: None,
+                receive_to_ack_ms: None,
+                send_to_timeout_ms: Some(9_000),
+            },
+        ];
+        let lines: Vec<String> = trace.to_string().lines().map(str::to_string).collect();
+        assert_eq!(lines[0], "send funds:");
+        assert!(lines[1].ends_with("3000ms"));
+        assert!(lines[2].contains("juno-1 -> osmosis-1 receive") && lines[2].ends_with("6000ms"));
+        assert!(lines[3].contains("osmosis-1 -> juno-1 ack") && lines[3].ends_with('?'));
+        assert!(lines[4].contains("  osmosis-1 timeout") && lines[4].ends_with("9000ms"));
+        // No ack or timeout span came back, so the total is unknown.
+        assert!(lines[5].ends_with('?'));
+    }
+}
@@ -0,0 +1,206 @@
+use abstract_interface::{AbstractAccount, ManagerExecFns};
+use abstract_std::{ibc_client, proxy, PROXY};
+use cosmwasm_std::{coins, Uint128};
+use cw_asset::{Asset, AssetInfo};
+use cw_orch::daemon::{CosmTxResponse, Daemon};
+use cw_orch::prelude::*;
+use log::warn;
+
+use crate::amount::{decimals, query_balance, resolve_asset, AmountSpec, ResolvedAmount};
+use crate::error::{IcaaError, IcaaResult};
+use crate::guard;
+use crate::run_log;
+
+/// Gas we keep aside in the wallet for the transaction itself.
+pub const GAS_RESERVE: u64 = 300_000;
+
+/// Fee for [`GAS_RESERVE`] at the chain's gas price, in the gas denom.
+pub fn gas_reserve(chain: &Daemon) -> Uint128 {
+    let gas_price = chain.state().chain_data.gas_price;
+    Uint128::new((GAS_RESERVE as f64 * gas_price).ceil() as u128)
+}
+
+/// Fail unless the wallet holds enough gas denom for the tx fee plus `spend`.
+pub fn ensure_gas(chain: &Daemon, spend: Uint128) -> IcaaResult<()> {
+    let gas_denom = chain.state().chain_data.gas_denom.clone();
+    let required = gas_reserve(chain) + spend;
+    let balance = query_balance(chain, &AssetInfo::native(&gas_denom), chain.sender())?;
+    if balance < required {
+        return Err(IcaaError::InsufficientBalance {
+            holder: format!("wallet {}", chain.sender()),
+            asset: format!("{gas_denom} (including gas)"),
+            balance,
+            required,
+        });
+    }
+    Ok(())
+}
+
+/// Amount of the gas denom that is spent on top of the fee when sending `funds`.
+fn gas_denom_spend(chain: &Daemon, funds: &ResolvedAmount) -> Uint128 {
+    match &funds.info {
+        AssetInfo::Native(denom) if denom == &chain.state().chain_data.gas_denom => funds.amount,
+        _ => Uint128::zero(),
+    }
+}
+
+/// Fail unless the wallet holds `funds` and the gas to send them.
+pub fn check_deposit(chain: &Daemon, funds: &ResolvedAmount) -> IcaaResult<()> {
+    ensure_gas(chain, gas_denom_spend(chain, funds))?;
+    let wallet_balance = query_balance(chain, &funds.info, chain.sender())?;
+    run_log::balance(chain, chain.sender(), &funds.entry, wallet_balance);
+    if wallet_balance < funds.amount {
+        return Err(IcaaError::InsufficientBalance {
+            holder: format!("wallet {}", chain.sender()),
+            asset: funds.entry.to_string(),
+            balance: wallet_balance,
+            required: funds.amount,
+        });
+    }
+    Ok(())
+}
+
+/// Send funds from the wallet to the account's proxy.
+pub fn deposit(
+    chain: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    funds: &ResolvedAmount,
+) -> IcaaResult<CosmTxResponse> {
+    check_deposit(chain, funds)?;
+
+    guard::spend(
+        chain,
+        &funds.info.inner(),
+        funds.amount,
+        &format!("deposit to account {}", account.id()?),
+    )?;
+
+    let proxy_addr = account.proxy.address()?;
+    warn!(
+        "Depositing {} {} to {}",
+        funds.amount, funds.entry, proxy_addr
+    );
+    let resp = match &funds.info {
+        AssetInfo::Native(denom) => chain.rt_handle.block_on(
+            chain
+                .daemon
+                .sender
+                .bank_send(proxy_addr.as_str(), coins(funds.amount.u128(), denom)),
+        )?,
+        AssetInfo::Cw20(addr) => chain.execute(
+            &cw20::Cw20ExecuteMsg::Transfer {
+                recipient: proxy_addr.to_string(),
+                amount: funds.amount,
+            },
+            &[],
+            addr,
+        )?,
+        info => return Err(IcaaError::UnsupportedAsset(info.to_string())),
+    };
+    run_log::tx("deposit", chain, Some(account.id()?.to_string()), &resp);
+    Ok(resp)
+}
+
+/// Send funds from the account's proxy back to the wallet.
+pub fn withdraw(
+    chain: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    funds: &ResolvedAmount,
+) -> IcaaResult<CosmTxResponse> {
+    ensure_gas(chain, Uint128::zero())?;
+    let proxy_addr = account.proxy.address()?;
+    let account_balance = query_balance(chain, &funds.info, &proxy_addr)?;
+    run_log::balance(chain, &proxy_addr, &funds.entry, account_balance);
+    if account_balance < funds.amount {
+        return Err(IcaaError::InsufficientBalance {
+            holder: format!("account {}", account.id()?),
+            asset: funds.entry.to_string(),
+            balance: account_balance,
+            required: funds.amount,
+        });
+    }
+
+    warn!(
+        "Withdrawing {} {} from {} to {}",
+        funds.amount,
+        funds.entry,
+        proxy_addr,
+        chain.sender()
+    );
+    let transfer = Asset::new(funds.info.clone(), funds.amount)
+        .transfer_msg(chain.sender())
+        .map_err(anyhow::Error::from)?;
+    let resp = account.manager.execute_on_module(
+        PROXY,
+        proxy::ExecuteMsg::ModuleAction {
+            msgs: vec![transfer],
+        },
+    )?;
+    run_log::tx("withdraw", chain, Some(account.id()?.to_string()), &resp);
+    Ok(resp)
+}
+
+/// Send funds from the account to its remote account on `host_chain` over ICS-20.
+///
+/// `spec` is evaluated against the proxy balance right before sending, so funds deposited
+/// earlier in the same run are included. `ibc_fee` is what relayers or fee middleware take on
+/// the way, in base units; the send is refused unless something is left for the remote side.
+pub fn send_funds(
+    chain: &Daemon,
+    account: &AbstractAccount<Daemon>,
+    host_chain: &str,
+    asset: &str,
+    spec: &AmountSpec,
+    ibc_fee: Uint128,
+) -> IcaaResult<CosmTxResponse> {
+    let (entry, info) = resolve_asset(chain, asset)?;
+    let AssetInfo::Native(denom) = &info else {
+        return Err(IcaaError::NonNativeAsset {
+            entry: entry.to_string(),
+            info: info.to_string(),
+        });
+    };
+
+    let proxy_addr = account.proxy.address()?;
+    let balance = query_balance(chain, &info, &proxy_addr)?;
+    run_log::balance(chain, &proxy_addr, &entry, balance);
+    if balance.is_zero() {
+        return Err(IcaaError::EmptyBalance {
+            holder: format!("account {}", account.id()?),
+            asset: entry.to_string(),
+        });
+    }
+    let amount = spec.evaluate(balance, || decimals(chain, &info))?;
+    if amount <= ibc_fee {
+        return Err(IcaaError::InvalidAmount(format!(
+            "sending {amount} {entry} to {host_chain} leaves nothing after an IBC fee of {ibc_fee}"
+        )));
+    }
+
+    guard::spend(chain, denom, amount, &format!("send to {host_chain}"))?;
+
+    warn!(
+        "Sending {} of {} {} to {} ({} expected on arrival)",
+        amount,
+        balance,
+        entry,
+        host_chain,
+        amount - ibc_fee
+    );
+    let resp = account.manager.execute_on_module(
+        PROXY,
+        proxy::ExecuteMsg::IbcAction {
+            msg: ibc_client::ExecuteMsg::SendFunds {
+                host_chain: host_chain.into(),
+                funds: coins(amount.u128(), denom),
+            },
+        },
+    )?;
+    run_log::tx(
+        &format!("send funds to {host_chain}"),
+        chain,
+        Some(account.id()?.to_string()),
+        &resp,
+    );
+    Ok(resp)
+}
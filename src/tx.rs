@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use cosmrs::bank::MsgSend;
+use cosmrs::cosmwasm::MsgExecuteContract;
+use cosmrs::tx::Msg;
+use cosmrs::{AccountId, Any, Denom};
+use cosmwasm_std::{BankMsg, Coin, CosmosMsg, WasmMsg};
+use cw_orch::daemon::{CosmTxResponse, Daemon};
+use cw_orch::prelude::*;
+use serde::Serialize;
+
+fn account_id(addr: &str) -> anyhow::Result<AccountId> {
+    AccountId::from_str(addr).map_err(|e| anyhow::anyhow!("invalid address {addr}: {e}"))
+}
+
+fn cosmos_coins(coins: &[Coin]) -> anyhow::Result<Vec<cosmrs::Coin>> {
+    coins
+        .iter()
+        .map(|coin| {
+            Ok(cosmrs::Coin {
+                denom: Denom::from_str(&coin.denom)
+                    .map_err(|e| anyhow::anyhow!("invalid denom {}: {e}", coin.denom))?,
+                amount: coin.amount.u128(),
+            })
+        })
+        .collect()
+}
+
+/// A wasm execute from the wallet, ready to be committed with [`commit`].
+pub fn execute_msg<M: Serialize>(
+    chain: &Daemon,
+    contract: &str,
+    msg: &M,
+    funds: &[Coin],
+) -> anyhow::Result<Any> {
+    execute_raw_msg(
+        chain.sender().as_str(),
+        contract,
+        serde_json::to_vec(msg)?,
+        funds,
+    )
+}
+
+fn execute_raw_msg(
+    sender: &str,
+    contract: &str,
+    msg: Vec<u8>,
+    funds: &[Coin],
+) -> anyhow::Result<Any> {
+    MsgExecuteContract {
+        sender: account_id(sender)?,
+        contract: account_id(contract)?,
+        msg,
+        funds: cosmos_coins(funds)?,
+    }
+    .into_any()
+    .map_err(|e| anyhow::anyhow!("failed to encode execute msg: {e}"))
+}
+
+/// A bank send from the wallet, ready to be committed with [`commit`].
+pub fn bank_send_msg(chain: &Daemon, to: &str, coins: &[Coin]) -> anyhow::Result<Any> {
+    bank_send_raw_msg(chain.sender().as_str(), to, coins)
+}
+
+fn bank_send_raw_msg(sender: &str, to: &str, coins: &[Coin]) -> anyhow::Result<Any> {
+    MsgSend {
+        from_address: account_id(sender)?,
+        to_address: account_id(to)?,
+        amount: cosmos_coins(coins)?,
+    }
+    .into_any()
+    .map_err(|e| anyhow::anyhow!("failed to encode bank send: {e}"))
+}
+
+/// Sign and broadcast all `msgs` in a single transaction.
+pub fn commit(chain: &Daemon, msgs: Vec<Any>) -> anyhow::Result<CosmTxResponse> {
+    Ok(chain
+        .rt_handle
+        .block_on(chain.daemon.sender.commit_tx_any(msgs, None))?)
+}
+
+/// Convert a wasm execute or bank send [`CosmosMsg`] into a message signed by the wallet.
+pub fn cosmos_msg_to_any(chain: &Daemon, msg: &CosmosMsg) -> anyhow::Result<Any> {
+    cosmos_msg_to_any_from(chain.sender().as_str(), msg)
+}
+
+/// Convert a wasm execute or bank send [`CosmosMsg`] into a message sent by `sender`.
+pub fn cosmos_msg_to_any_from(sender: &str, msg: &CosmosMsg) -> anyhow::Result<Any> {
+    match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds,
+        }) => execute_raw_msg(sender, contract_addr, msg.to_vec(), funds),
+        CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+            bank_send_raw_msg(sender, to_address, amount)
+        }
+        msg => anyhow::bail!("can't send {msg:?} from {sender}"),
+    }
+}
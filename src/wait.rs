@@ -0,0 +1,315 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use cw_orch::daemon::{CosmTxResponse, Daemon};
+use cw_orch::environment::{NodeQuerier, QueryHandler};
+use cw_orch_interchain::prelude::{DaemonInterchainEnv, InterchainEnv};
+use cw_orch_interchain::types::NestedPacketsFlow;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{chain_id, run_log};
+
+/// Waits that were detached or ran past their deadline, one JSON object per line.
+pub const PENDING_FILE: &str = "pending-ibc.jsonl";
+
+/// How long to wait for the packets of a transaction and how to report progress.
+#[derive(Debug, Clone)]
+pub struct WaitStrategy {
+    /// Give up waiting after this long, recording the tx so the wait can be resumed.
+    pub deadline: Option<Duration>,
+    pub progress_every: Duration,
+    /// Don't wait at all, only record the tx.
+    pub detach: bool,
+}
+
+impl Default for WaitStrategy {
+    fn default() -> Self {
+        Self {
+            deadline: None,
+            progress_every: Duration::from_secs(30),
+            detach: false,
+        }
+    }
+}
+
+impl WaitStrategy {
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn progress_every(mut self, every: Duration) -> Self {
+        self.progress_every = every;
+        self
+    }
+
+    pub fn detach(mut self, detach: bool) -> Self {
+        self.detach = detach;
+        self
+    }
+}
+
+/// A transaction whose packets we stopped waiting for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingWait {
+    pub step: String,
+    pub chain_id: String,
+    pub txhash: String,
+    pub height: u64,
+}
+
+pub enum WaitOutcome {
+    Done(NestedPacketsFlow<Daemon>),
+    Detached(PendingWait),
+}
+
+impl WaitOutcome {
+    /// The flow, or an error if the wait was detached.
+    pub fn done(self) -> anyhow::Result<NestedPacketsFlow<Daemon>> {
+        match self {
+            WaitOutcome::Done(flow) => Ok(flow),
+            WaitOutcome::Detached(pending) => anyhow::bail!(
+                "detached from {} ({}), resume with `icaa --chain {} wait {}`",
+                pending.step,
+                pending.txhash,
+                pending.chain_id,
+                pending.txhash
+            ),
+        }
+    }
+}
+
+/// The packets of a transaction weren't relayed before the deadline.
+#[derive(Debug, Clone)]
+pub struct DeadlineExceeded {
+    pub pending: PendingWait,
+    pub waited: Duration,
+}
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} not relayed after {}s, resume with `icaa --chain {} wait {}`",
+            self.pending.step,
+            self.waited.as_secs(),
+            self.pending.chain_id,
+            self.pending.txhash
+        )
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+/// Wait for the packets sent by `tx` on `home` according to `strategy`.
+///
+/// The packets are written to the run log once the wait completes, the caller logs `tx` itself.
+pub fn wait(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    step: &str,
+    tx: CosmTxResponse,
+    strategy: &WaitStrategy,
+) -> anyhow::Result<WaitOutcome> {
+    let pending = PendingWait {
+        step: step.to_string(),
+        chain_id: chain_id(home),
+        txhash: tx.txhash.clone(),
+        height: tx.height,
+    };
+    if strategy.detach {
+        record(&pending)?;
+        warn!("Detached from {} ({})", step, pending.txhash);
+        return Ok(WaitOutcome::Detached(pending));
+    }
+
+    let packets = sent_packets(&tx);
+    let (sender, receiver) = mpsc::channel();
+    let waiter = interchain.clone();
+    let home_chain_id = pending.chain_id.clone();
+    // Not joined on purpose: past the deadline the wait is left running until the process exits.
+    std::thread::spawn(move || {
+        let _ = sender.send(
+            waiter
+                .wait_ibc(&home_chain_id, tx)
+                .map_err(anyhow::Error::from),
+        );
+    });
+
+    let start = Instant::now();
+    loop {
+        let timeout = match strategy.deadline {
+            Some(deadline) => match deadline.checked_sub(start.elapsed()) {
+                Some(left) => left.min(strategy.progress_every),
+                None => {
+                    record(&pending)?;
+                    return Err(DeadlineExceeded {
+                        pending,
+                        waited: start.elapsed(),
+                    }
+                    .into());
+                }
+            },
+            None => strategy.progress_every,
+        };
+        match receiver.recv_timeout(timeout) {
+            Ok(flow) => {
+                let flow = flow?;
+                run_log::flow(step, &flow);
+                return Ok(WaitOutcome::Done(flow));
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let blocks = home
+                    .node_querier()
+                    .block_height()
+                    .map(|height| height.saturating_sub(pending.height).to_string())
+                    .unwrap_or_else(|_| "?".into());
+                warn!(
+                    "Waiting on {} for {}s: packets {} sent on {}, {} blocks since inclusion",
+                    step,
+                    start.elapsed().as_secs(),
+                    packets.join(", "),
+                    pending.chain_id,
+                    blocks
+                );
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                anyhow::bail!("IBC wait for {} stopped without a result", step)
+            }
+        }
+    }
+}
+
+/// Wait again for a transaction recorded by a detached or expired [`wait`].
+pub fn resume(
+    interchain: &DaemonInterchainEnv,
+    home: &Daemon,
+    txhash: &str,
+    strategy: &WaitStrategy,
+) -> anyhow::Result<WaitOutcome> {
+    let step = pending()?
+        .into_iter()
+        .find(|pending| pending.txhash == txhash)
+        .map(|pending| pending.step)
+        .unwrap_or_else(|| format!("tx {txhash}"));
+    let tx = home.node_querier().find_tx(txhash.to_string())?;
+    let outcome = wait(interchain, home, &step, tx, &strategy.clone().detach(false))?;
+    if matches!(outcome, WaitOutcome::Done(_)) {
+        forget(txhash)?;
+    }
+    Ok(outcome)
+}
+
+/// The `port/channel#sequence` of every packet sent by `tx`.
+fn sent_packets(tx: &CosmTxResponse) -> Vec<String> {
+    tx.get_events("send_packet")
+        .iter()
+        .map(|event| {
+            let attr = |key| event.get_first_attribute_value(key).unwrap_or_default();
+            format!(
+                "{}/{}#{}",
+                attr("packet_src_port"),
+                attr("packet_src_channel"),
+                attr("packet_sequence")
+            )
+        })
+        .collect()
+}
+
+/// Every recorded wait that wasn't resumed yet.
+pub fn pending() -> anyhow::Result<Vec<PendingWait>> {
+    read_pending(Path::new(PENDING_FILE))
+}
+
+/// Record `wait`, replacing an earlier record of the same tx, e.g. when a resumed wait expires
+/// again.
+fn record(wait: &PendingWait) -> anyhow::Result<()> {
+    record_in(Path::new(PENDING_FILE), wait)
+}
+
+fn forget(txhash: &str) -> anyhow::Result<()> {
+    forget_in(Path::new(PENDING_FILE), txhash)
+}
+
+fn read_pending(path: &Path) -> anyhow::Result<Vec<PendingWait>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+fn record_in(path: &Path, wait: &PendingWait) -> anyhow::Result<()> {
+    let mut waits = read_pending(path)?;
+    waits.retain(|pending| pending.txhash != wait.txhash);
+    waits.push(wait.clone());
+    write_pending(path, &waits)
+}
+
+fn forget_in(path: &Path, txhash: &str) -> anyhow::Result<()> {
+    let mut waits = read_pending(path)?;
+    waits.retain(|pending| pending.txhash != txhash);
+    write_pending(path, &waits)
+}
+
+/// Rewrite the pending file at `path` with `waits`, removing it when there are none left.
+fn write_pending(path: &Path, waits: &[PendingWait]) -> anyhow::Result<()> {
+    if waits.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    let lines: Vec<String> = waits
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<_, _>>()?;
+    fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending_wait(txhash: &str, height: u64) -> PendingWait {
+        PendingWait {
+            step: format!("send {txhash}"),
+            chain_id: "juno-1".to_string(),
+            txhash: txhash.to_string(),
+            height,
+        }
+    }
+
+    #[test]
+    fn pending_waits_roundtrip_through_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PENDING_FILE);
+        assert!(read_pending(&path).unwrap().is_empty());
+
+        record_in(&path, &pending_wait("AAAA", 1)).unwrap();
+        record_in(&path, &pending_wait("BBBB", 2)).unwrap();
+        // Recording a tx again replaces its earlier record.
+        record_in(&path, &pending_wait("AAAA", 3)).unwrap();
+        let waits = read_pending(&path).unwrap();
+        let recorded: Vec<_> = waits
+            .iter()
+            .map(|wait| (wait.txhash.as_str(), wait.height))
+            .collect();
+        assert_eq!(recorded, [("BBBB", 2), ("AAAA", 3)]);
+        assert_eq!(waits[1].step, "send AAAA");
+
+        forget_in(&path, "BBBB").unwrap();
+        forget_in(&path, "CCCC").unwrap();
+        assert_eq!(read_pending(&path).unwrap().len(), 1);
+        forget_in(&path, "AAAA").unwrap();
+        assert!(!path.exists());
+    }
+}